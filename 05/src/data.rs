@@ -1,69 +1,174 @@
+use std::collections::{HashMap, VecDeque};
+
+use common::interval::IntervalMap;
+
 #[derive(Debug, Clone)]
-pub struct AlmanacMapLine {
-    pub dst_start: usize,
-    pub src_start: usize,
-    pub len: usize,
+struct AlmanacMapLine {
+    dst_start: usize,
+    src_start: usize,
+    len: usize,
+}
+
+impl AlmanacMapLine {
+    fn src(&self) -> std::ops::Range<usize> {
+        self.src_start..self.src_start + self.len
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct AlmanacMap {
-    pub lines: Vec<AlmanacMapLine>,
+    pub from: String,
+    pub to: String,
+    pub map: IntervalMap,
 }
 
 #[derive(Debug, Clone)]
 pub struct Almanac {
     pub seeds: Vec<usize>,
-    pub seed_to_soil_map: AlmanacMap,
-    pub soil_to_fertilizer_map: AlmanacMap,
-    pub fertilizer_to_water_map: AlmanacMap,
-    pub water_to_light_map: AlmanacMap,
-    pub light_to_temperature_map: AlmanacMap,
-    pub temperature_to_humidity_map: AlmanacMap,
-    pub humidity_to_location_map: AlmanacMap,
+    pub maps: Vec<AlmanacMap>,
 }
 
-fn parse_almanac_map(input: &str) -> AlmanacMap {
-    AlmanacMap {
-        lines: input
-            .lines()
-            .skip(1)
-            .map(|line| {
-                let mut num_it = line.split_ascii_whitespace().map(str::parse::<usize>);
-                AlmanacMapLine {
-                    dst_start: num_it.next().expect("missing destination start").unwrap(),
-                    src_start: num_it.next().expect("missing source start").unwrap(),
-                    len: num_it.next().expect("missing map len").unwrap(),
+impl Almanac {
+    /// The maps to apply in order to convert `from` into `to`
+    pub fn path(&self, from: &str, to: &str) -> Result<Vec<&AlmanacMap>, String> {
+        // breadth first search over categories, remembering the map used to
+        // reach each one
+        let mut reached_by: HashMap<&str, Option<&AlmanacMap>> = HashMap::new();
+        reached_by.insert(from, None);
+        let mut queue = VecDeque::from([from]);
+        while let Some(category) = queue.pop_front() {
+            if category == to {
+                break;
+            }
+            for map in self.maps.iter().filter(|m| m.from == category) {
+                if !reached_by.contains_key(map.to.as_str()) {
+                    reached_by.insert(&map.to, Some(map));
+                    queue.push_back(&map.to);
                 }
-            })
-            .collect(),
+            }
+        }
+        if !reached_by.contains_key(to) {
+            let mut reachable = reached_by.into_keys().collect::<Vec<_>>();
+            reachable.sort();
+            return Err(format!(
+                "no conversion from '{from}' to '{to}', only reaching {}",
+                reachable.join(", ")
+            ));
+        }
+        let mut path = vec![];
+        let mut category = to;
+        while let Some(map) = reached_by[category] {
+            path.push(map);
+            category = &map.from;
+        }
+        path.reverse();
+        Ok(path)
+    }
+}
+
+fn parse_almanac_map(input: &str) -> Result<AlmanacMap, String> {
+    let mut lines = input.lines();
+    let header = lines.next().ok_or("empty map section")?;
+    let (from, to) = header
+        .strip_suffix(" map:")
+        .and_then(|names| names.split_once("-to-"))
+        .ok_or(format!("expecting a 'X-to-Y map:' header, got '{header}'"))?;
+    let mut lines = lines
+        .map(|line| {
+            let nums = line
+                .split_ascii_whitespace()
+                .map(str::parse::<usize>)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("invalid number in '{line}' of {header} {e}"))?;
+            match nums.as_slice() {
+                &[dst_start, src_start, len] => Ok(AlmanacMapLine {
+                    dst_start,
+                    src_start,
+                    len,
+                }),
+                _ => Err(format!("expecting three numbers in '{line}' of {header}")),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    lines.sort_by_key(|line| line.src_start);
+    if let Some(w) = lines
+        .windows(2)
+        .find(|w| w[0].src_start + w[0].len > w[1].src_start)
+    {
+        return Err(format!(
+            "overlapping source ranges {:?} and {:?} in {header}",
+            w[0].src(),
+            w[1].src()
+        ));
+    }
+
+    let mut map = IntervalMap::new();
+    for line in &lines {
+        map.insert(line.src(), line.dst_start);
+    }
+    // make every lookup below the last line hit a segment
+    if let Some(last) = lines.last() {
+        map.fill_gaps(0..last.src().end);
+    }
+    Ok(AlmanacMap {
+        from: from.to_owned(),
+        to: to.to_owned(),
+        map,
+    })
+}
+
+/// Kahn's algorithm, errors with the categories involved in cycles
+fn check_acyclic(maps: &[AlmanacMap]) -> Result<(), String> {
+    let mut in_degree: HashMap<&str, usize> = HashMap::new();
+    for map in maps {
+        in_degree.entry(&map.from).or_default();
+        *in_degree.entry(&map.to).or_default() += 1;
+    }
+    let mut queue = in_degree
+        .iter()
+        .filter(|(_, &deg)| deg == 0)
+        .map(|(&cat, _)| cat)
+        .collect::<Vec<_>>();
+    while let Some(category) = queue.pop() {
+        in_degree.remove(category);
+        for map in maps.iter().filter(|m| m.from == category) {
+            let deg = in_degree.get_mut(map.to.as_str()).unwrap();
+            *deg -= 1;
+            if *deg == 0 {
+                queue.push(&map.to);
+            }
+        }
+    }
+    if in_degree.is_empty() {
+        return Ok(());
     }
+    let mut cyclic = in_degree.into_keys().collect::<Vec<_>>();
+    cyclic.sort();
+    Err(format!("maps form a cycle between {}", cyclic.join(", ")))
 }
 
-pub fn parse_almanac(input: &str) -> Almanac {
+pub fn parse_almanac(input: &str) -> Result<Almanac, String> {
     let mut section_it = input.split("\n\n");
     let seeds = section_it
         .next()
-        .unwrap()
+        .and_then(|s| s.strip_prefix("seeds:"))
+        .ok_or("expecting seeds first")?
         .split_ascii_whitespace()
-        .skip(1)
         .map(str::parse::<usize>)
         .collect::<Result<Vec<_>, _>>()
-        .unwrap();
-    let seed_to_soil_map = parse_almanac_map(section_it.next().unwrap());
-    let soil_to_fertilizer_map = parse_almanac_map(section_it.next().unwrap());
-    let fertilizer_to_water_map = parse_almanac_map(section_it.next().unwrap());
-    let water_to_light_map = parse_almanac_map(section_it.next().unwrap());
-    let light_to_temperature_map = parse_almanac_map(section_it.next().unwrap());
-    let temperature_to_humidity_map = parse_almanac_map(section_it.next().unwrap());
-    let humidity_to_location_map = parse_almanac_map(section_it.next().unwrap());
-    Almanac {
-        seeds,
-        seed_to_soil_map,
-        soil_to_fertilizer_map,
-        fertilizer_to_water_map,
-        water_to_light_map,
-        light_to_temperature_map,
-        temperature_to_humidity_map,
-        humidity_to_location_map,
+        .map_err(|e| format!("invalid seed number {e}"))?;
+    let maps = section_it
+        .filter(|s| !s.trim().is_empty())
+        .map(parse_almanac_map)
+        .collect::<Result<Vec<_>, _>>()?;
+    for (idx, map) in maps.iter().enumerate() {
+        if maps[..idx]
+            .iter()
+            .any(|m| m.from == map.from && m.to == map.to)
+        {
+            return Err(format!("duplicate {}-to-{} map", map.from, map.to));
+        }
     }
+    check_acyclic(&maps)?;
+    Ok(Almanac { seeds, maps })
 }
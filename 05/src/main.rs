@@ -1,25 +1,43 @@
 mod data;
 
-use std::{env, fs};
+use std::{
+    env, fs,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
+use common::interval::{IntervalMap, IntervalSet};
 use data::*;
 use itertools::Itertools;
 
 fn main() {
     let input_file_path = env::args().nth(1).unwrap_or("05/example_input.txt".into());
     let input = fs::read_to_string(&input_file_path).unwrap();
-    let almanac = parse_almanac(&input);
+    let almanac = parse_almanac(&input).unwrap();
+
+    if let Some(conversion) = env::args()
+        .skip(2)
+        .find_map(|a| a.strip_prefix("--convert=").map(str::to_owned))
+    {
+        let (from, to) = conversion
+            .split_once("-to-")
+            .expect("expecting --convert=X-to-Y");
+        let path = almanac.path(from, to).unwrap();
+        let names = std::iter::once(from)
+            .chain(path.iter().map(|m| m.to.as_str()))
+            .join(" -> ");
+        println!("{names}:\n{}", compose_path(from, &path));
+    }
+
+    let maps = almanac.path("seed", "location").unwrap();
+    let seed_to_location_map = compose_path("seed", &maps);
+    if env::args().skip(2).any(|a| a == "--breakpoints") {
+        println!("Seed to location map:\n{seed_to_location_map}");
+    }
 
     let lowest_location = almanac
         .seeds
         .iter()
-        .map(|&x| almanac.seed_to_soil_map.lookup(x))
-        .map(|x| almanac.soil_to_fertilizer_map.lookup(x))
-        .map(|x| almanac.fertilizer_to_water_map.lookup(x))
-        .map(|x| almanac.water_to_light_map.lookup(x))
-        .map(|x| almanac.light_to_temperature_map.lookup(x))
-        .map(|x| almanac.temperature_to_humidity_map.lookup(x))
-        .map(|x| almanac.humidity_to_location_map.lookup(x))
+        .map(|&x| seed_to_location_map.lookup(x))
         .min()
         .unwrap();
 
@@ -35,75 +53,267 @@ fn main() {
             let &len = seed_range.next().unwrap();
             start..start + len
         })
-        .collect::<Vec<_>>();
-    let location_ranges = almanac.humidity_to_location_map.lookup_ranges(
-        &almanac.temperature_to_humidity_map.lookup_ranges(
-            &almanac.light_to_temperature_map.lookup_ranges(
-                &almanac.water_to_light_map.lookup_ranges(
-                    &almanac.fertilizer_to_water_map.lookup_ranges(
-                        &almanac.soil_to_fertilizer_map.lookup_ranges(
-                            &almanac.seed_to_soil_map.lookup_ranges(&seed_ranges),
-                        ),
-                    ),
-                ),
-            ),
-        ),
-    );
-    let lowest_location_seed_ranges = location_ranges
-        .iter()
-        .map(|range| range.start)
-        .min()
-        .unwrap();
+        .collect::<IntervalSet>();
+    let location_ranges = seed_to_location_map.lookup_ranges(&seed_ranges);
+    let lowest_location_seed_ranges = location_ranges.min().unwrap();
 
     println!("Lowest location number considering seed-ranges for part 2: {lowest_location_seed_ranges}");
+
+    if env::args().skip(2).any(|a| a == "--verify-reverse") {
+        let reverse_walk = lowest_location_by_reverse_walk(&maps, &seed_ranges);
+        println!("Lowest location number walking back from locations: {reverse_walk:?}");
+        assert_eq!(reverse_walk, Some(lowest_location_seed_ranges));
+        let seeds = seed_to_location_map
+            .reverse_lookup(lowest_location_seed_ranges)
+            .into_iter()
+            .filter(|&seed| seed_ranges.contains(seed))
+            .collect::<Vec<_>>();
+        println!("Seeds with the lowest location: {seeds:?}");
+    }
+
+    if env::args().skip(2).any(|a| a == "--brute-force") {
+        let brute_force =
+            lowest_location_by_brute_force(&seed_to_location_map, &seed_ranges);
+        println!("Lowest location number looking up every seed: {brute_force:?}");
+        assert_eq!(brute_force, Some(lowest_location_seed_ranges));
+    }
+}
+
+/// A single map equivalent to applying all `maps` in order, starting at `from`
+fn compose_path(from: &str, maps: &[&AlmanacMap]) -> AlmanacMap {
+    let identity = AlmanacMap {
+        from: from.to_owned(),
+        to: from.to_owned(),
+        map: IntervalMap::new(),
+    };
+    maps.iter().fold(identity, |acc, map| acc.compose(map))
+}
+
+/// Look up every single seed, spread over all available cores
+fn lowest_location_by_brute_force(
+    seed_to_location: &AlmanacMap,
+    seeds: &IntervalSet,
+) -> Option<usize> {
+    const CHUNK: usize = 1 << 20;
+    let chunks = seeds
+        .ranges()
+        .iter()
+        .flat_map(|r| {
+            r.clone()
+                .step_by(CHUNK)
+                .map(|start| start..r.end.min(start + CHUNK))
+        })
+        .collect::<Vec<_>>();
+    let next_chunk = AtomicUsize::new(0);
+    let n_threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    std::thread::scope(|scope| {
+        let workers = (0..n_threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut lowest = None;
+                    while let Some(chunk) =
+                        chunks.get(next_chunk.fetch_add(1, Ordering::Relaxed))
+                    {
+                        let chunk_lowest = chunk
+                            .clone()
+                            .map(|seed| seed_to_location.lookup(seed))
+                            .min();
+                        lowest = lowest.into_iter().chain(chunk_lowest).min();
+                    }
+                    lowest
+                })
+            })
+            .collect::<Vec<_>>();
+        workers.into_iter().filter_map(|w| w.join().unwrap()).min()
+    })
+}
+
+/// Find the lowest location reachable from `seeds` by mapping ever larger
+/// location ranges `0..end` back to seeds.
+fn lowest_location_by_reverse_walk(
+    maps: &[&AlmanacMap],
+    seeds: &IntervalSet,
+) -> Option<usize> {
+    let reaches_seeds = |end: usize| {
+        let seeds_for_locations = maps
+            .iter()
+            .rev()
+            .fold(IntervalSet::from(0..end), |set, map| {
+                map.reverse_lookup_ranges(&set)
+            });
+        !seeds_for_locations.intersection(seeds).is_empty()
+    };
+    // double until some seed is reached, then bisect for the first location
+    let mut end = 1;
+    while !reaches_seeds(end) {
+        if end == usize::MAX {
+            return None;
+        }
+        end = end.saturating_mul(2);
+    }
+    let (mut lo, mut hi) = (end / 2, end);
+    while lo + 1 < hi {
+        let mid = lo + (hi - lo) / 2;
+        if reaches_seeds(mid) {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+    Some(hi - 1)
 }
 
 impl AlmanacMap {
     fn lookup(&self, index: usize) -> usize {
-        self.lines
+        self.map.get(index)
+    }
+
+    fn lookup_ranges(&self, ranges: &IntervalSet) -> IntervalSet {
+        self.map.map_set(ranges)
+    }
+
+    /// All indices that map to `index`
+    fn reverse_lookup(&self, index: usize) -> Vec<usize> {
+        self.reverse_lookup_ranges(&IntervalSet::from(index..index + 1))
+            .ranges()
             .iter()
-            .find(|mapline| {
-                (mapline.src_start..mapline.src_start + mapline.len).contains(&index)
-            })
-            .map(|mapline| index - mapline.src_start + mapline.dst_start)
-            .unwrap_or(index.clone())
-    }
-
-    fn lookup_range(&self, range: std::ops::Range<usize>) -> Vec<std::ops::Range<usize>> {
-        let mut result = vec![];
-        let mut bound = range.start;
-        while bound < range.end {
-            if let Some(mapline) = self.lines.iter().find(|mapline| {
-                (mapline.src_start..mapline.src_start + mapline.len).contains(&bound)
-            }) {
-                let end = std::cmp::min(range.end, mapline.src_start + mapline.len);
-                result.push(
-                    bound + mapline.dst_start - mapline.src_start
-                        ..end + mapline.dst_start - mapline.src_start,
-                );
-                bound = end;
-            } else if let Some(mapline) = self
-                .lines
-                .iter()
-                .find(|mapline| bound < mapline.src_start && mapline.src_start < range.end)
-            {
-                result.push(bound..mapline.src_start);
-                bound = mapline.src_start;
-            } else {
-                result.push(bound..range.end);
-                bound = range.end;
-            }
+            .cloned()
+            .flatten()
+            .collect()
+    }
+
+    fn reverse_lookup_ranges(&self, ranges: &IntervalSet) -> IntervalSet {
+        self.map.preimage(ranges)
+    }
+
+    /// The map applying `self` first and `other` second
+    fn compose(&self, other: &AlmanacMap) -> AlmanacMap {
+        AlmanacMap {
+            from: self.from.clone(),
+            to: other.to.clone(),
+            map: self.map.compose(&other.map),
         }
-        result
     }
+}
 
-    fn lookup_ranges(
-        &self,
-        ranges: &Vec<std::ops::Range<usize>>,
-    ) -> Vec<std::ops::Range<usize>> {
-        ranges.iter().fold(vec![], |mut acc, range| {
-            acc.append(&mut self.lookup_range(range.clone()));
-            acc
-        })
+/// Lists the breakpoints, one mapped source range per line
+impl std::fmt::Display for AlmanacMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for segment in self.map.segments() {
+            let offset = segment.dst_start as i128 - segment.src.start as i128;
+            writeln!(
+                f,
+                "{:>12} ..{:>12} -> {:>12} ..{:>12} ({offset:+})",
+                segment.src.start,
+                segment.src.end,
+                segment.dst_start,
+                segment.map(segment.src.end - 1) + 1,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn composed_map_matches_chain() {
+        let input = fs::read_to_string("example_input.txt").unwrap();
+        let almanac = parse_almanac(&input).unwrap();
+        let maps = almanac.path("seed", "location").unwrap();
+        let composed = compose_path("seed", &maps);
+        for seed in 0..200 {
+            let chained = maps.iter().fold(seed, |x, map| map.lookup(x));
+            assert_eq!(composed.lookup(seed), chained, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn reverse_lookups() {
+        let input = fs::read_to_string("example_input.txt").unwrap();
+        let almanac = parse_almanac(&input).unwrap();
+        let maps = almanac.path("seed", "location").unwrap();
+        let seed_to_soil = maps[0];
+        assert_eq!(seed_to_soil.reverse_lookup(81), [79]);
+        assert_eq!(seed_to_soil.reverse_lookup(51), [99]);
+        assert_eq!(seed_to_soil.reverse_lookup(14), [14]);
+        let soil = IntervalSet::from(50..60);
+        assert_eq!(
+            seed_to_soil.reverse_lookup_ranges(&soil).ranges(),
+            [50..58, 98..100]
+        );
+
+        let seeds = [79..93, 55..68].into_iter().collect();
+        assert_eq!(lowest_location_by_reverse_walk(&maps, &seeds), Some(46));
+        let seeds = [79..80, 14..15, 55..56, 13..14].into_iter().collect();
+        assert_eq!(lowest_location_by_reverse_walk(&maps, &seeds), Some(35));
+    }
+
+    #[test]
+    fn resolves_conversion_paths() {
+        let input = fs::read_to_string("example_input.txt").unwrap();
+        let almanac = parse_almanac(&input).unwrap();
+        let path = almanac.path("soil", "light").unwrap();
+        let names = path.iter().map(|m| m.to.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["fertilizer", "water", "light"]);
+        assert!(almanac.path("seed", "seed").unwrap().is_empty());
+        assert!(almanac.path("light", "soil").is_err());
+
+        // sections in any order
+        let shuffled = "seeds: 1\n\nb-to-c map:\n10 0 5\n\na-to-b map:\n0 3 2\n";
+        let almanac = parse_almanac(shuffled).unwrap();
+        let map = compose_path("a", &almanac.path("a", "c").unwrap());
+        assert_eq!((map.lookup(3), map.lookup(4), map.lookup(7)), (10, 11, 7));
+    }
+
+    #[test]
+    fn rejects_gaps_and_cycles() {
+        let gap = "seeds: 1\n\na-to-b map:\n0 3 2\n\nc-to-d map:\n0 3 2\n";
+        let almanac = parse_almanac(gap).unwrap();
+        assert_eq!(
+            almanac.path("a", "d").unwrap_err(),
+            "no conversion from 'a' to 'd', only reaching a, b"
+        );
+        let cycle = "seeds: 1\n\na-to-b map:\n0 3 2\n\nb-to-a map:\n0 3 2\n";
+        assert_eq!(
+            parse_almanac(cycle).unwrap_err(),
+            "maps form a cycle between a, b"
+        );
+        assert!(parse_almanac("seeds: 1\n\na-to-b map:\n0 3\n").is_err());
+        assert!(parse_almanac("seeds: 1\n\nnot a header\n0 3 2\n").is_err());
+    }
+
+    #[test]
+    fn validates_and_sorts_map_lines() {
+        let overlapping = "seeds: 1\n\na-to-b map:\n0 5 5\n20 8 2\n";
+        assert_eq!(
+            parse_almanac(overlapping).unwrap_err(),
+            "overlapping source ranges 5..10 and 8..10 in a-to-b map:"
+        );
+        let unsorted = "seeds: 1\n\na-to-b map:\n0 10 5\n20 2 3\n";
+        let almanac = parse_almanac(unsorted).unwrap();
+        let srcs = almanac.maps[0]
+            .map
+            .segments()
+            .iter()
+            .map(|s| s.src.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(srcs, [0..2, 2..5, 5..10, 10..15]);
+        let map = &almanac.maps[0];
+        assert_eq!(
+            (map.lookup(3), map.lookup(7), map.lookup(12), map.lookup(99)),
+            (21, 7, 2, 99)
+        );
+    }
+
+    #[test]
+    fn brute_force_agrees() {
+        let input = fs::read_to_string("example_input.txt").unwrap();
+        let almanac = parse_almanac(&input).unwrap();
+        let map = compose_path("seed", &almanac.path("seed", "location").unwrap());
+        let seeds = [79..93, 55..68].into_iter().collect();
+        assert_eq!(lowest_location_by_brute_force(&map, &seeds), Some(46));
     }
 }
@@ -1,77 +1,95 @@
+mod racesheet;
+
 use std::{env, fs};
 
+use crate::racesheet::RaceSheet;
+
 fn main() {
     let input_file_path = env::args().nth(1).unwrap_or("06/example_input.txt".into());
     let input = fs::read_to_string(&input_file_path).unwrap();
-    let times = input
-        .lines()
-        .next()
-        .unwrap()
-        .split_whitespace()
-        .skip(1)
-        .map(|t_str| str::parse::<usize>(t_str).unwrap());
-    let records = input
-        .lines()
-        .skip(1)
-        .next()
+    let sheet: RaceSheet = input.parse().unwrap();
+    let p1_product = sheet
+        .races()
         .unwrap()
-        .split_whitespace()
-        .skip(1)
-        .map(|t_str| str::parse::<usize>(t_str).unwrap());
-    let races = times
-        .zip(records)
-        .map(|(t, d)| Race {
-            time_ms: t,
-            record_mm: d,
-        })
-        .collect::<Vec<_>>();
-    let p1_product = races
-        .iter()
-        .cloned()
+        .into_iter()
         .map(count_winning_options)
-        .product::<usize>();
+        .product::<u64>();
     println!("Product of margins for part 1: {p1_product}");
 
-    let p2_time = input
-        .lines()
-        .next()
-        .unwrap()
-        .chars()
-        .filter(|&c| c.is_numeric())
-        .collect::<String>()
-        .parse::<usize>()
-        .unwrap();
-    let p2_record = input
-        .lines()
-        .skip(1)
-        .next()
-        .unwrap()
-        .chars()
-        .filter(|&c| c.is_numeric())
-        .collect::<String>()
-        .parse::<usize>()
-        .unwrap();
-    // brute forcing a quadratic equation but ok
-    let p2_options = count_winning_options(Race {
-        time_ms: p2_time,
-        record_mm: p2_record,
-    });
+    let p2_options = count_winning_options(sheet.kerned_race().unwrap());
     println!("Margin for part 2: {p2_options}");
 }
 
 #[derive(Clone, Copy)]
 struct Race {
-    time_ms: usize,
-    record_mm: usize,
+    time_ms: u64,
+    record_mm: u64,
 }
 
-fn count_winning_options(race: Race) -> usize {
-    (0..race.time_ms)
-        .filter(|t_push| {
-            let dist = t_push * (race.time_ms - t_push);
-            dist > race.record_mm
-        })
-        .count()
-        .try_into()
-        .unwrap()
+/// Number of integer push times `t` with `t * (time - t) > record`.
+///
+/// The winning push times lie strictly between the roots of
+/// `t^2 - time * t + record`, the integer square root of the discriminant gets
+/// us within one of the lower root and the boundary is then corrected exactly.
+fn count_winning_options(race: Race) -> u64 {
+    let (time, record) = (race.time_ms as u128, race.record_mm as u128);
+    let wins = |t: u128| t * (time - t) > record;
+    let Some(disc) = (time * time).checked_sub(4 * record) else {
+        return 0;
+    };
+    let mut lowest = (time - disc.isqrt()) / 2;
+    while lowest <= time / 2 && !wins(lowest) {
+        lowest += 1;
+    }
+    while lowest > 0 && wins(lowest - 1) {
+        lowest -= 1;
+    }
+    if lowest > time / 2 {
+        return 0;
+    }
+    // the winning push times are symmetric around time / 2
+    (time - 2 * lowest + 1) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count_winning_options_brute_force(race: Race) -> u64 {
+        (0..race.time_ms)
+            .filter(|t_push| {
+                let dist = t_push * (race.time_ms - t_push);
+                dist > race.record_mm
+            })
+            .count() as u64
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        for time_ms in 0..80 {
+            for record_mm in 0..time_ms * time_ms / 4 + 3 {
+                let race = Race { time_ms, record_mm };
+                assert_eq!(
+                    count_winning_options(race),
+                    count_winning_options_brute_force(race),
+                    "time {time_ms}, record {record_mm}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn solves_huge_races() {
+        let race = |time_ms, record_mm| Race { time_ms, record_mm };
+        assert_eq!(count_winning_options(race(71530, 940200)), 71503);
+        // exactly on a root, the record is not beaten at t = 2^31
+        let half = 1u64 << 31;
+        assert_eq!(count_winning_options(race(2 * half, half * half - 1)), 1);
+        assert_eq!(count_winning_options(race(2 * half, half * half)), 0);
+        assert_eq!(count_winning_options(race(u64::MAX, 0)), u64::MAX - 1);
+        assert_eq!(
+            count_winning_options(race(u64::MAX, u64::MAX)),
+            u64::MAX - 3
+        );
+    }
 }
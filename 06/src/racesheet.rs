@@ -0,0 +1,123 @@
+use std::str::FromStr;
+
+use crate::Race;
+
+/// The `Time:` and `Distance:` lines of a race sheet, one column per race.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RaceSheet {
+    time_columns: Vec<String>,
+    distance_columns: Vec<String>,
+}
+
+fn parse_row<'a>(line: Option<&'a str>, header: &str) -> Result<Vec<&'a str>, String> {
+    let line = line.ok_or(format!("missing '{header}' line"))?;
+    let columns = line
+        .strip_prefix(header)
+        .ok_or(format!("expecting '{header}' line, got '{line}'"))?
+        .split_whitespace()
+        .collect::<Vec<_>>();
+    if let Some(col) = columns
+        .iter()
+        .find(|col| !col.chars().all(|c| c.is_ascii_digit()))
+    {
+        return Err(format!("invalid number '{col}' in '{header}' line"));
+    }
+    Ok(columns)
+}
+
+fn parse_number(digits: &str) -> Result<u64, String> {
+    digits
+        .parse()
+        .map_err(|e| format!("could not parse '{digits}': {e}"))
+}
+
+impl FromStr for RaceSheet {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut lines = value.lines().filter(|l| !l.trim().is_empty());
+        let times = parse_row(lines.next(), "Time:")?;
+        let distances = parse_row(lines.next(), "Distance:")?;
+        if let Some(extra) = lines.next() {
+            return Err(format!("unexpected line '{extra}' after distances"));
+        }
+        if times.len() != distances.len() {
+            return Err(format!(
+                "{} times but {} distances",
+                times.len(),
+                distances.len()
+            ));
+        }
+        if times.is_empty() {
+            return Err("race sheet without races".to_owned());
+        }
+        Ok(RaceSheet {
+            time_columns: times.into_iter().map(str::to_owned).collect(),
+            distance_columns: distances.into_iter().map(str::to_owned).collect(),
+        })
+    }
+}
+
+impl RaceSheet {
+    /// Every column is a separate race
+    pub fn races(&self) -> Result<Vec<Race>, String> {
+        self.time_columns
+            .iter()
+            .zip(&self.distance_columns)
+            .map(|(t, d)| {
+                Ok(Race {
+                    time_ms: parse_number(t)?,
+                    record_mm: parse_number(d)?,
+                })
+            })
+            .collect()
+    }
+
+    /// The columns are a single race with bad kerning
+    pub fn kerned_race(&self) -> Result<Race, String> {
+        Ok(Race {
+            time_ms: parse_number(&self.time_columns.concat())?,
+            record_mm: parse_number(&self.distance_columns.concat())?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_both_interpretations() {
+        let sheet: RaceSheet = "Time:      7  15   30\nDistance:  9  40  200\n"
+            .parse()
+            .unwrap();
+        let races = sheet.races().unwrap();
+        let times = races.iter().map(|r| r.time_ms).collect::<Vec<_>>();
+        let records = races.iter().map(|r| r.record_mm).collect::<Vec<_>>();
+        assert_eq!((times, records), (vec![7, 15, 30], vec![9, 40, 200]));
+        let kerned = sheet.kerned_race().unwrap();
+        assert_eq!((kerned.time_ms, kerned.record_mm), (71530, 940200));
+    }
+
+    #[test]
+    fn reports_malformed_sheets() {
+        let err = |s: &str| s.parse::<RaceSheet>().unwrap_err();
+        assert_eq!(err("Time: 7 15\nDistance: 9\n"), "2 times but 1 distances");
+        assert_eq!(err("Time: 7\n"), "missing 'Distance:' line");
+        assert_eq!(
+            err("Distance: 9\nTime: 7\n"),
+            "expecting 'Time:' line, got 'Distance: 9'"
+        );
+        assert_eq!(
+            err("Time: 7 x5\nDistance: 9 4\n"),
+            "invalid number 'x5' in 'Time:' line"
+        );
+        assert_eq!(err("Time:\nDistance:\n"), "race sheet without races");
+
+        let huge: RaceSheet = "Time: 99999999999 99999999999\nDistance: 1 2\n"
+            .parse()
+            .unwrap();
+        assert!(huge.races().is_ok());
+        assert!(huge.kerned_race().is_err());
+    }
+}
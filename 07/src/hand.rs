@@ -0,0 +1,81 @@
+pub const CARDS: [char; 13] = [
+    'A', 'K', 'Q', 'J', 'T', '9', '8', '7', '6', '5', '4', '3', '2',
+];
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Hand {
+    pub cards: [char; 5],
+    pub bid: u32,
+}
+
+impl std::fmt::Debug for Hand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{} {}",
+            self.cards.iter().collect::<String>(),
+            self.bid
+        ))
+    }
+}
+
+fn cards_from_str(cards: &str) -> Result<[char; 5], String> {
+    if cards.len() != 5 {
+        return Err("hand str must be 5 characters long".to_string());
+    }
+    if let Some(c) = cards.chars().find(|c| !CARDS.contains(c)) {
+        return Err(format!("invalid card character '{c}'"));
+    }
+    let mut chars = cards.chars();
+    let cards = [
+        chars.next().unwrap(),
+        chars.next().unwrap(),
+        chars.next().unwrap(),
+        chars.next().unwrap(),
+        chars.next().unwrap(),
+    ];
+    Ok(cards)
+}
+
+impl TryFrom<&str> for Hand {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let (cards, bid) = value.split_once(' ').ok_or(format!(
+            "Error parsing hand, expecting two words for hand and bid, got '{value}'"
+        ))?;
+        let cards = cards_from_str(cards)?;
+        Ok(Hand {
+            cards,
+            bid: bid
+                .parse()
+                .map_err(|_| format!("Could not parse bid amount in '{value}'"))?,
+        })
+    }
+}
+
+// the lower the stronger
+#[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub enum Type {
+    FiveOfAKind,
+    FourOfAKind,
+    FullHouse,
+    ThreeOfAKind,
+    TwoPair,
+    OnePair,
+    HighCard,
+}
+
+impl Type {
+    /// The type given the counts of the two most common cards
+    pub fn from_sorted_counts(first: u8, second: u8) -> Self {
+        match (first, second) {
+            (5, _) => Type::FiveOfAKind,
+            (4, _) => Type::FourOfAKind,
+            (3, 2) => Type::FullHouse,
+            (3, _) => Type::ThreeOfAKind,
+            (2, 2) => Type::TwoPair,
+            (2, _) => Type::OnePair,
+            _ => Type::HighCard,
+        }
+    }
+}
@@ -1,296 +1,120 @@
-use std::{env, fs};
+mod hand;
+mod rules;
+
+use std::{collections::BTreeMap, env, fs};
+
+use crate::{
+    hand::{Hand, Type},
+    rules::Rules,
+};
 
 fn main() {
     let input_file_path = env::args().nth(1).unwrap_or("07/example_input.txt".into());
     let input = fs::read_to_string(&input_file_path).unwrap();
-    let mut hands = input
+    let hands = input
         .lines()
         .map(|l| l.try_into().unwrap())
         .collect::<Vec<Hand>>();
-    hands.sort();
-    let ranks = (1..=hands.len()).rev();
-    let p1_winnings: usize = hands
-        .iter()
-        .zip(ranks.clone())
-        .map(|(hand, rank)| hand.bid as usize * rank)
-        .sum();
-    println!("Winnings in part 1: {p1_winnings}");
-    let mut jhands = input
-        .lines()
-        .map(|l| l.try_into().unwrap())
-        .collect::<Vec<JHand>>();
-    jhands.sort();
-    let p2_winnings: usize = jhands
-        .iter()
-        .zip(ranks)
-        .map(|(hand, rank)| hand.bid as usize * rank)
-        .sum();
-    println!("Winnings in part 2: {p2_winnings}");
-}
-
-#[derive(Clone, PartialEq, Eq, Ord)]
-struct Hand {
-    cards: [char; 5],
-    bid: u32,
-}
-
-impl std::fmt::Debug for Hand {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!(
-            "{} {}",
-            &self.cards.iter().collect::<String>(),
-            self.bid
-        ))
-    }
-}
-
-fn cards_from_str(cards: &str) -> Result<[char; 5], String> {
-    if cards.len() != 5 {
-        return Err("hand str must be 5 characters long".to_string());
-    }
-    if let Some(c) = cards.chars().find(|c| {
-        ![
-            'A', 'K', 'Q', 'J', 'T', '9', '8', '7', '6', '5', '4', '3', '2',
-        ]
-        .contains(&c)
-    }) {
-        return Err(format!("invalid card character '{c}'"));
-    }
-    let mut chars = cards.chars();
-    let cards = [
-        chars.next().unwrap(),
-        chars.next().unwrap(),
-        chars.next().unwrap(),
-        chars.next().unwrap(),
-        chars.next().unwrap(),
-    ];
-    Ok(cards)
-}
-
-impl TryFrom<&str> for Hand {
-    type Error = String;
-
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let (cards, bid) = value.split_once(' ').ok_or(format!(
-            "Error parsing hand, expecting two words for hand and bid, got '{value}'"
-        ))?;
-        let cards = cards_from_str(cards)?;
-        Ok(Hand {
-            cards,
-            bid: bid
-                .parse()
-                .map_err(|_| format!("Could not parse bid amount in '{value}'"))?,
-        })
-    }
-}
-
-#[derive(Debug, PartialOrd, Ord, PartialEq, Eq)]
-enum Type {
-    FiveOfAKind,
-    FourOfAKind,
-    FullHouse,
-    ThreeOfAKind,
-    TwoPair,
-    OnePair,
-    HighCard,
-}
-
-impl Hand {
-    fn histogram(&self) -> [u8; 13] {
-        let mut counts = [0u8; 13];
-        for x in self.cards {
-            let bin = [
-                'A', 'K', 'Q', 'J', 'T', '9', '8', '7', '6', '5', '4', '3', '2',
-            ]
-            .iter()
-            .position(|&b| b == x)
-            .unwrap();
-            counts[bin as usize] += 1;
-        }
-        counts
+    let explain = env::args().skip(2).any(|a| a == "--explain");
+    for (part, rules) in [(1, Rules::standard()), (2, Rules::jokers())] {
+        if explain {
+            println!(
+                "Ranking in part {part}:\n{}",
+                explain_ranking(&hands, &rules)
+            );
+        }
+        let winnings = total_winnings(&hands, &rules);
+        println!("Winnings in part {part}: {winnings}");
     }
-
-    fn determine_type(&self) -> Type {
-        let hist = self.histogram();
-        if hist.contains(&5) {
-            return Type::FiveOfAKind;
-        }
-        if hist.contains(&4) {
-            return Type::FourOfAKind;
-        }
-        if hist.contains(&3) && hist.contains(&2) {
-            return Type::FullHouse;
-        }
-        if hist.contains(&3) {
-            return Type::ThreeOfAKind;
-        }
-        if hist.iter().filter(|x| **x == 2).count() == 2 {
-            return Type::TwoPair;
-        }
-        if hist.contains(&2) {
-            return Type::OnePair;
-        }
-        Type::HighCard
-    }
-}
-
-// the lower the stronger
-fn strength(card: char) -> u8 {
-    [
-        'A', 'K', 'Q', 'J', 'T', '9', '8', '7', '6', '5', '4', '3', '2',
-    ]
-    .iter()
-    .position(|&b| b == card)
-    .unwrap() as u8
 }
 
-fn compare_hands_of_same_type(h1: [char; 5], h2: [char; 5]) -> Option<std::cmp::Ordering> {
-    h1.iter()
-        .zip(h2.iter())
-        .find(|(x, y)| x != y)
-        .map(|(x, y)| strength(*x).partial_cmp(&strength(*y)))
-        .unwrap_or(Some(core::cmp::Ordering::Equal))
+/// The hands from strongest to weakest
+fn rank_hands(hands: &[Hand], rules: &Rules) -> Vec<Hand> {
+    let mut hands = hands.to_vec();
+    hands.sort_by(|h1, h2| rules.compare(h1, h2));
+    hands
 }
 
-impl PartialOrd for Hand {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        match self.determine_type().partial_cmp(&other.determine_type()) {
-            Some(core::cmp::Ordering::Equal) => {
-                compare_hands_of_same_type(self.cards, other.cards)
-            }
-            ord => return ord,
-        }
-    }
-}
-
-#[derive(Clone, PartialEq, Eq, Ord)]
-struct JHand {
-    cards: [char; 5],
-    bid: u32,
-}
-
-impl std::fmt::Debug for JHand {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!(
-            "{} {}\t{:?}",
-            &self.cards.iter().collect::<String>(),
-            self.bid,
-            self.determine_type()
-        ))
-    }
-}
-
-impl TryFrom<&str> for JHand {
-    type Error = String;
-
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let (cards, bid) = value.split_once(' ').ok_or(format!(
-            "Error parsing hand, expecting two words for hand and bid, got '{value}'"
-        ))?;
-        let cards = cards_from_str(cards)?;
-        Ok(JHand {
-            cards,
-            bid: bid
-                .parse()
-                .map_err(|_| format!("Could not parse bid amount in '{value}'"))?,
-        })
-    }
-}
-
-impl JHand {
-    fn histogram(&self) -> [u8; 13] {
-        let mut counts = [0u8; 13];
-        for x in self.cards {
-            let bin = [
-                'A', 'K', 'Q', 'T', '9', '8', '7', '6', '5', '4', '3', '2', 'J',
-            ]
-            .iter()
-            .position(|&b| b == x)
-            .unwrap();
-            counts[bin as usize] += 1;
-        }
-        counts
-    }
-
-    fn determine_type(&self) -> Type {
-        let hist = self.histogram();
-
-        let n_equal_with_j = |n, hist: [u8; 13]| -> Option<[u8; 13]> {
-            let jokers = hist[12];
-            if let Some((pos, jokers_used)) = (0..=jokers).find_map(|jokers_used| {
-                hist[0..12]
-                    .iter()
-                    .position(|&x| x == (n - jokers_used))
-                    .map(|pos| (pos, jokers_used))
-            }) {
-                let mut new_hist = hist.clone();
-                new_hist[pos] -= n - jokers_used;
-                new_hist[12] -= jokers_used;
-                return Some(new_hist);
-            }
-            if hist[12] >= n {
-                let mut new_hist = hist.clone();
-                new_hist[12] -= n;
-                return Some(new_hist);
-            }
-            None
+fn total_winnings(hands: &[Hand], rules: &Rules) -> usize {
+    let hands = rank_hands(hands, rules);
+    let ranks = (1..=hands.len()).rev();
+    hands
+        .iter()
+        .zip(ranks)
+        .map(|(hand, rank)| hand.bid as usize * rank)
+        .sum()
+}
+
+/// One line per hand, strongest first, with its rank, type, what its wild
+/// cards stand for and how it beats the next weaker hand, followed by a
+/// histogram of the types
+fn explain_ranking(hands: &[Hand], rules: &Rules) -> String {
+    let ranked = rank_hands(hands, rules);
+    let mut lines = vec![];
+    let mut histogram: BTreeMap<Type, usize> = BTreeMap::new();
+    for (idx, hand) in ranked.iter().enumerate() {
+        let hand_type = rules.determine_type(hand);
+        *histogram.entry(hand_type).or_default() += 1;
+        let wild = match rules.substitution(hand) {
+            Some(card) => format!("wild as {card}"),
+            None => String::new(),
         };
-
-        if n_equal_with_j(5, hist).is_some() {
-            return Type::FiveOfAKind;
-        }
-        if n_equal_with_j(4, hist).is_some() {
-            return Type::FourOfAKind;
-        }
-        if let Some(hist) = n_equal_with_j(3, hist) {
-            if n_equal_with_j(2, hist).is_some() {
-                return Type::FullHouse;
-            }
-        }
-        if n_equal_with_j(3, hist).is_some() {
-            return Type::ThreeOfAKind;
-        }
-        if n_equal_with_j(2, hist)
-            .and_then(|hist| n_equal_with_j(2, hist))
-            .is_some()
-        {
-            return Type::TwoPair;
-        }
-        if n_equal_with_j(2, hist).is_some() {
-            return Type::OnePair;
-        }
-        Type::HighCard
+        let beats = match ranked.get(idx + 1) {
+            None => "weakest hand".to_owned(),
+            Some(next) => match rules.deciding_card(hand, next) {
+                Some(pos) => format!(
+                    "beats {next:?} on card {} '{}'",
+                    pos + 1,
+                    rules.tie_break_cards(hand)[pos]
+                ),
+                None if rules.determine_type(next) == hand_type => {
+                    format!("ties {next:?}")
+                }
+                None => format!("beats {next:?} on type"),
+            },
+        };
+        lines.push(format!(
+            "{:>5} {:<10} {:<13} {wild:<10} {beats}",
+            ranked.len() - idx,
+            format!("{hand:?}"),
+            format!("{hand_type:?}"),
+        ));
     }
-}
-
-// the lower the stronger
-fn strength_j(card: char) -> u8 {
-    [
-        'A', 'K', 'Q', 'T', '9', '8', '7', '6', '5', '4', '3', '2', 'J',
-    ]
-    .iter()
-    .position(|&b| b == card)
-    .unwrap() as u8
-}
-
-fn compare_hands_of_same_type_j(
-    h1: [char; 5],
-    h2: [char; 5],
-) -> Option<std::cmp::Ordering> {
-    h1.iter()
-        .zip(h2.iter())
-        .find(|(x, y)| x != y)
-        .map(|(x, y)| strength_j(*x).partial_cmp(&strength_j(*y)))
-        .unwrap_or(Some(core::cmp::Ordering::Equal))
-}
-
-impl PartialOrd for JHand {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        match self.determine_type().partial_cmp(&other.determine_type()) {
-            Some(core::cmp::Ordering::Equal) => {
-                compare_hands_of_same_type_j(self.cards, other.cards)
-            }
-            ord => return ord,
-        }
+    let most = histogram.values().copied().max().unwrap_or(0).max(1);
+    for (hand_type, count) in histogram {
+        lines.push(format!(
+            "{:<13} {count:>5} {}",
+            format!("{hand_type:?}"),
+            "#".repeat((count * 40).div_ceil(most))
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_winnings() {
+        let input = fs::read_to_string("example_input.txt").unwrap();
+        let hands = input
+            .lines()
+            .map(|l| l.try_into().unwrap())
+            .collect::<Vec<Hand>>();
+        assert_eq!(total_winnings(&hands, &Rules::standard()), 6440);
+        assert_eq!(total_winnings(&hands, &Rules::jokers()), 5905);
+
+        let explained = explain_ranking(&hands, &Rules::jokers());
+        let lines = explained.lines().collect::<Vec<_>>();
+        assert!(lines[0].starts_with("    5 KTJJT 220"), "{}", lines[0]);
+        assert!(lines[0].contains("FourOfAKind   wild as T"), "{}", lines[0]);
+        assert!(
+            lines[1].ends_with("beats T55J5 684 on card 1 'Q'"),
+            "{}",
+            lines[1]
+        );
+        assert!(lines[4].ends_with("weakest hand"), "{}", lines[4]);
+        assert!(lines[5].starts_with("FourOfAKind       3 ####"));
     }
 }
@@ -0,0 +1,257 @@
+use std::cmp::Ordering;
+
+use crate::hand::{Hand, Type, CARDS};
+
+/// How to order hands of the same type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    /// compare the cards one by one in the order they were dealt
+    InOrder,
+    /// compare the cards from strongest to weakest, like poker
+    Sorted,
+}
+
+/// A variant of Camel Cards
+#[derive(Debug, Clone)]
+pub struct Rules {
+    /// the cards from strongest to weakest
+    pub order: [char; 13],
+    /// cards that pretend to be whatever makes the hand strongest
+    pub wild: Vec<char>,
+    pub tie_break: TieBreak,
+}
+
+impl Rules {
+    pub fn standard() -> Self {
+        Rules {
+            order: CARDS,
+            wild: vec![],
+            tie_break: TieBreak::InOrder,
+        }
+    }
+
+    /// Jacks are jokers, wild but the weakest card on their own
+    pub fn jokers() -> Self {
+        Rules {
+            order: [
+                'A', 'K', 'Q', 'T', '9', '8', '7', '6', '5', '4', '3', '2', 'J',
+            ],
+            wild: vec!['J'],
+            tie_break: TieBreak::InOrder,
+        }
+    }
+
+    // the lower the stronger
+    pub fn strength(&self, card: char) -> u8 {
+        self.order.iter().position(|&c| c == card).unwrap() as u8
+    }
+
+    /// Count of each non-wild card by strength, and the number of wild cards
+    fn histogram(&self, hand: &Hand) -> ([u8; 13], u8) {
+        let mut counts = [0u8; 13];
+        let mut wild = 0;
+        for card in hand.cards {
+            if self.wild.contains(&card) {
+                wild += 1;
+            } else {
+                counts[self.strength(card) as usize] += 1;
+            }
+        }
+        (counts, wild)
+    }
+
+    /// Wild cards always do best by joining the most common other card
+    pub fn determine_type(&self, hand: &Hand) -> Type {
+        let (mut counts, wild) = self.histogram(hand);
+        counts.sort_unstable_by(|a, b| b.cmp(a));
+        counts[0] += wild;
+        Type::from_sorted_counts(counts[0], counts[1])
+    }
+
+    /// The card every wild card in `hand` stands for, if it has any
+    pub fn substitution(&self, hand: &Hand) -> Option<char> {
+        let (counts, wild) = self.histogram(hand);
+        if wild == 0 {
+            return None;
+        }
+        // strongest of the most common cards, or the strongest card overall
+        // for a hand of only wild cards
+        let (strength, _) = counts
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|&(_, &count)| count)
+            .unwrap();
+        let mut cards = self.order[strength..].iter();
+        cards.find(|c| !self.wild.contains(c)).copied()
+    }
+
+    /// The cards in the order they are compared to break ties
+    pub fn tie_break_cards(&self, hand: &Hand) -> [char; 5] {
+        let mut cards = hand.cards;
+        if self.tie_break == TieBreak::Sorted {
+            cards.sort_by_key(|&c| self.strength(c));
+        }
+        cards
+    }
+
+    /// Position in [`Rules::tie_break_cards`] of the card that decides between
+    /// two hands of the same type, `None` if the types differ or the hands tie
+    pub fn deciding_card(&self, h1: &Hand, h2: &Hand) -> Option<usize> {
+        if self.determine_type(h1) != self.determine_type(h2) {
+            return None;
+        }
+        let (c1, c2) = (self.tie_break_cards(h1), self.tie_break_cards(h2));
+        (0..5).find(|&idx| self.strength(c1[idx]) != self.strength(c2[idx]))
+    }
+
+    /// `Ordering::Less` if `h1` is the stronger hand
+    pub fn compare(&self, h1: &Hand, h2: &Hand) -> Ordering {
+        self.determine_type(h1)
+            .cmp(&self.determine_type(h2))
+            .then_with(|| {
+                let strengths = |h| self.tie_break_cards(h).map(|c| self.strength(c));
+                strengths(h1).cmp(&strengths(h2))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hand(cards: &str) -> Hand {
+        format!("{cards} 1").as_str().try_into().unwrap()
+    }
+
+    /// Without wild cards, the number of equal pairs of cards tells the type
+    fn type_by_pairs(cards: &[char; 5]) -> Type {
+        let pairs = (0..5)
+            .flat_map(|i| (i + 1..5).map(move |j| (i, j)))
+            .filter(|&(i, j)| cards[i] == cards[j])
+            .count();
+        match pairs {
+            10 => Type::FiveOfAKind,
+            6 => Type::FourOfAKind,
+            4 => Type::FullHouse,
+            3 => Type::ThreeOfAKind,
+            2 => Type::TwoPair,
+            1 => Type::OnePair,
+            _ => Type::HighCard,
+        }
+    }
+
+    /// The strongest type over every way of replacing the `wild` cards from
+    /// position `from` onwards
+    fn type_by_substitution(wild: &[char], mut cards: [char; 5], from: usize) -> Type {
+        let Some(idx) = (from..5).find(|&idx| wild.contains(&cards[idx])) else {
+            return type_by_pairs(&cards);
+        };
+        let mut best = Type::HighCard;
+        for card in CARDS {
+            cards[idx] = card;
+            best = best.min(type_by_substitution(wild, cards, idx + 1));
+        }
+        best
+    }
+
+    #[test]
+    fn histogram_matches_substitution_for_every_hand() {
+        let rules = Rules::jokers();
+        let mut cards = [0; 5];
+        for n in 0..13usize.pow(5) {
+            let mut rest = n;
+            for card in &mut cards {
+                *card = rest % 13;
+                rest /= 13;
+            }
+            let hand = Hand {
+                cards: cards.map(|c| CARDS[c]),
+                bid: 0,
+            };
+            assert_eq!(
+                rules.determine_type(&hand),
+                type_by_substitution(&rules.wild, hand.cards, 0),
+                "{hand:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn classifies_hands() {
+        let (standard, jokers) = (Rules::standard(), Rules::jokers());
+        for (cards, standard_type, joker_type) in [
+            ("32T3K", Type::OnePair, Type::OnePair),
+            ("T55J5", Type::ThreeOfAKind, Type::FourOfAKind),
+            ("KK677", Type::TwoPair, Type::TwoPair),
+            ("KTJJT", Type::TwoPair, Type::FourOfAKind),
+            ("QQQJA", Type::ThreeOfAKind, Type::FourOfAKind),
+            ("JJJJJ", Type::FiveOfAKind, Type::FiveOfAKind),
+            ("2345J", Type::HighCard, Type::OnePair),
+            ("2233J", Type::TwoPair, Type::FullHouse),
+        ] {
+            assert_eq!(
+                standard.determine_type(&hand(cards)),
+                standard_type,
+                "{cards}"
+            );
+            assert_eq!(jokers.determine_type(&hand(cards)), joker_type, "{cards}");
+        }
+    }
+
+    #[test]
+    fn explains_substitutions_and_tie_breaks() {
+        let jokers = Rules::jokers();
+        assert_eq!(jokers.substitution(&hand("KTJJT")), Some('T'));
+        assert_eq!(jokers.substitution(&hand("2JA3K")), Some('A'));
+        assert_eq!(jokers.substitution(&hand("JJJJJ")), Some('A'));
+        assert_eq!(jokers.substitution(&hand("KKQQA")), None);
+        assert_eq!(Rules::standard().substitution(&hand("KTJJT")), None);
+
+        assert_eq!(
+            jokers.deciding_card(&hand("QQQJA"), &hand("T55J5")),
+            Some(0)
+        );
+        assert_eq!(
+            jokers.deciding_card(&hand("KKJ77"), &hand("KKJ66")),
+            Some(3)
+        );
+        assert_eq!(jokers.deciding_card(&hand("KKJ77"), &hand("KK677")), None);
+        assert_eq!(jokers.deciding_card(&hand("KK677"), &hand("KK677")), None);
+    }
+
+    #[test]
+    fn new_variants_are_rule_sets() {
+        let jacks_and_twos = Rules {
+            wild: vec!['J', '2'],
+            ..Rules::standard()
+        };
+        assert_eq!(
+            jacks_and_twos.determine_type(&hand("2J345")),
+            Type::ThreeOfAKind
+        );
+        assert_eq!(
+            jacks_and_twos.determine_type(&hand("2J3J4")),
+            Type::FourOfAKind
+        );
+
+        let sorted = Rules {
+            tie_break: TieBreak::Sorted,
+            ..Rules::standard()
+        };
+        let (low_first, high_first) = (hand("2AKQT"), hand("KQT9A"));
+        assert_eq!(
+            Rules::standard().compare(&low_first, &high_first),
+            Ordering::Greater
+        );
+        assert_eq!(sorted.compare(&low_first, &high_first), Ordering::Greater);
+        assert_eq!(
+            sorted.compare(&hand("2AKQT"), &hand("3AKQT")),
+            Ordering::Greater
+        );
+        assert_eq!(
+            sorted.compare(&hand("A2345"), &hand("5432A")),
+            Ordering::Equal
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{CamelMap, Periodicity};
+
+const CYCLE_COLORS: [&str; 6] = ["red", "blue", "darkgreen", "orange", "purple", "brown"];
+
+/// The edges `(node, instruction)` walked during one traversal of the cycle
+fn cycle_edges(map: &CamelMap, periodicity: &Periodicity) -> Vec<(usize, u8)> {
+    let mut edges = vec![];
+    let mut node = periodicity.start_node;
+    for inst in std::iter::repeat_n(map.instruction.bytes(), periodicity.period).flatten()
+    {
+        edges.push((node, inst));
+        node = map.step(node, inst);
+    }
+    edges
+}
+
+/// Graphviz rendering of the map, with `..A` nodes green, `..Z` nodes red, and
+/// the edges of each given cycle in its own color
+pub fn to_dot(map: &CamelMap, cycles: &[Periodicity]) -> String {
+    let mut edge_colors: HashMap<(usize, u8), &str> = HashMap::new();
+    for (periodicity, color) in cycles.iter().zip(CYCLE_COLORS.iter().cycle()) {
+        for edge in cycle_edges(map, periodicity) {
+            edge_colors.entry(edge).or_insert(color);
+        }
+    }
+
+    let name = |node: usize| String::from_utf8_lossy(&map.names[node]).into_owned();
+    let mut nodes = (0..map.names.len()).collect::<Vec<_>>();
+    nodes.sort_by_key(|&node| map.names[node]);
+    let mut dot = String::from("digraph camel_map {\n");
+    for node in &nodes {
+        let style = match map.names[*node][2] {
+            b'A' => " [style=filled, fillcolor=palegreen]",
+            b'Z' => " [style=filled, fillcolor=salmon]",
+            _ => "",
+        };
+        writeln!(dot, "    \"{}\"{style};", name(*node)).unwrap();
+    }
+    for node in nodes {
+        for inst in [b'L', b'R'] {
+            let color = match edge_colors.get(&(node, inst)) {
+                Some(color) => format!(", color={color}, penwidth=2"),
+                None => String::new(),
+            };
+            writeln!(
+                dot,
+                "    \"{}\" -> \"{}\" [label={}{color}];",
+                name(node),
+                name(map.step(node, inst)),
+                inst as char
+            )
+            .unwrap();
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_periodicity;
+
+    #[test]
+    fn exports_example() {
+        let input = std::fs::read_to_string("example_input_3.txt").unwrap();
+        let map: CamelMap = input.as_str().try_into().unwrap();
+        let dot = to_dot(&map, &[get_periodicity(&map, map.index[b"11A"])]);
+        assert!(dot.starts_with("digraph camel_map {\n"));
+        assert!(dot.contains("\"11A\" [style=filled, fillcolor=palegreen];"));
+        assert!(dot.contains("\"22Z\" [style=filled, fillcolor=salmon];"));
+        assert!(dot.contains("\"XXX\";"));
+        assert!(dot.contains("\"11B\" -> \"11Z\" [label=R, color=red, penwidth=2];"));
+        assert!(dot.contains("\"11B\" -> \"XXX\" [label=L];"));
+        assert!(dot.contains("\"11A\" -> \"11B\" [label=L];"));
+        assert_eq!(dot.matches(" -> ").count(), 16);
+    }
+}
@@ -1,6 +1,9 @@
+mod dot;
+
 use std::{collections::HashMap, env, fs};
 
-use itertools::{FoldWhile, Itertools};
+use common::{cycle::find_cycle, math::crt_all};
+use itertools::Itertools;
 
 type Node = [u8; 3];
 
@@ -8,10 +11,16 @@ fn node_from_str(string: &str) -> Node {
     string.as_bytes().try_into().unwrap()
 }
 
+/// The map with nodes interned to dense indices into `names`
 #[derive(Debug)]
 struct CamelMap {
     instruction: String,
-    mapping: HashMap<Node, (Node, Node)>,
+    names: Vec<Node>,
+    index: HashMap<Node, usize>,
+    left: Vec<usize>,
+    right: Vec<usize>,
+    /// node reached after one full pass over the instructions from each node
+    pass: Vec<usize>,
 }
 
 impl TryFrom<&str> for CamelMap {
@@ -21,20 +30,101 @@ impl TryFrom<&str> for CamelMap {
         let (instruction, mapping_str) = value
             .split_once("\n\n")
             .ok_or("expecting two sections".to_owned())?;
-        let mapping_re = regex::Regex::new(r"(\w+) = \((\w+), (\w+)\)").unwrap();
-        let mapping = mapping_re
-            .captures_iter(mapping_str)
-            .map(|caps| {
-                (
+        if instruction.is_empty() {
+            return Err("expecting at least one instruction".to_owned());
+        }
+        if let Some((pos, inst)) = instruction
+            .chars()
+            .enumerate()
+            .find(|(_, inst)| !matches!(inst, 'L' | 'R'))
+        {
+            return Err(format!(
+                "invalid instruction '{inst}' at position {}, expecting L or R",
+                pos + 1
+            ));
+        }
+
+        // the line number in the input of each node definition
+        let mapping_re = regex::Regex::new(r"^(\w{3}) = \((\w{3}), (\w{3})\)$").unwrap();
+        let first_line = instruction.lines().count() + 2;
+        let mapping = mapping_str
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(idx, line)| {
+                let caps = mapping_re.captures(line.trim()).ok_or(format!(
+                    "expecting 'XXX = (YYY, ZZZ)' on line {}, got '{line}'",
+                    first_line + idx
+                ))?;
+                Ok((
+                    first_line + idx,
                     node_from_str(&caps[1]),
                     (node_from_str(&caps[2]), node_from_str(&caps[3])),
-                )
+                ))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let names = mapping.iter().map(|&(_, node, _)| node).collect::<Vec<_>>();
+        let mut index = HashMap::new();
+        for &(line, node, _) in &mapping {
+            if index.insert(node, index.len()).is_some() {
+                return Err(format!(
+                    "node {} defined again on line {line}",
+                    String::from_utf8_lossy(&node)
+                ));
+            }
+        }
+        let (left, right) = mapping
+            .iter()
+            .map(|(line, _, (left, right))| {
+                let lookup = |node: &Node| {
+                    index.get(node).copied().ok_or(format!(
+                        "node {} referenced on line {line} is never defined",
+                        String::from_utf8_lossy(node)
+                    ))
+                };
+                Ok((lookup(left)?, lookup(right)?))
             })
-            .collect::<HashMap<_, _>>();
-        Ok(CamelMap {
+            .collect::<Result<(Vec<_>, Vec<_>), String>>()?;
+
+        let mut map = CamelMap {
             instruction: instruction.to_owned(),
-            mapping,
-        })
+            names,
+            index,
+            left,
+            right,
+            pass: vec![],
+        };
+        map.pass = (0..map.names.len())
+            .map(|node| {
+                map.instruction
+                    .bytes()
+                    .fold(node, |node, inst| map.step(node, inst))
+            })
+            .collect();
+        Ok(map)
+    }
+}
+
+impl CamelMap {
+    fn step(&self, node: usize, inst: u8) -> usize {
+        match inst {
+            b'L' => self.left[node],
+            b'R' => self.right[node],
+            _ => unreachable!("instructions are checked when parsing"),
+        }
+    }
+
+    fn node(&self, name: &str) -> Result<usize, String> {
+        name.as_bytes()
+            .try_into()
+            .ok()
+            .and_then(|node: Node| self.index.get(&node).copied())
+            .ok_or(format!("unknown node {name}"))
+    }
+
+    fn ends_with(&self, node: usize, letter: u8) -> bool {
+        self.names[node][2] == letter
     }
 }
 
@@ -42,108 +132,410 @@ impl TryFrom<&str> for CamelMap {
 struct Periodicity {
     start_cycle: usize,
     period: usize,
-    start_node: Node,
+    start_node: usize,
+}
+
+fn get_periodicity(map: &CamelMap, start: usize) -> Periodicity {
+    // the state is the node we are at when starting a pass over the instructions
+    let cycle = find_cycle(start, |&node| map.pass[node]);
+    Periodicity {
+        start_cycle: cycle.prefix_len,
+        period: cycle.period,
+        start_node: cycle.states[cycle.prefix_len],
+    }
 }
 
-fn get_periodicity(map: &CamelMap, mut start: Node) -> Periodicity {
-    let mut start_of_cycle_nodes: HashMap<Node, usize> = Default::default();
-    for (cycle, instructions) in std::iter::repeat(map.instruction.as_bytes()).enumerate()
+/// Number of steps to walk from `start` to `goal`, or an error if the walk
+/// settles into a cycle that never passes `goal`
+fn steps_to(map: &CamelMap, start: usize, goal: usize) -> Result<usize, String> {
+    // every node the walk will ever reach is seen by the end of the first
+    // traversal of its cycle
+    let periodicity = get_periodicity(map, start);
+    let passes = periodicity.start_cycle + periodicity.period;
+    let mut node = start;
+    for (idx, inst) in std::iter::repeat_n(map.instruction.as_bytes(), passes)
+        .flatten()
+        .enumerate()
     {
-        if let Some(start_cycle) = start_of_cycle_nodes.get(&start) {
-            return Periodicity {
-                start_cycle: *start_cycle,
-                period: cycle - start_cycle,
-                start_node: start,
-            };
-        }
-        start_of_cycle_nodes.insert(start, cycle);
-        for inst in instructions {
-            let branches = &map.mapping[&start];
-            start = match inst {
-                b'L' => branches.0,
-                b'R' => branches.1,
-                _ => panic!(),
-            }
+        if node == goal {
+            return Ok(idx);
         }
+        node = map.step(node, *inst);
     }
-    panic!();
+    let name = |node: usize| String::from_utf8_lossy(&map.names[node]).into_owned();
+    Err(format!(
+        "{} is unreachable from {}, which loops every {} steps after {} steps",
+        name(goal),
+        name(start),
+        periodicity.period * map.instruction.len(),
+        periodicity.start_cycle * map.instruction.len(),
+    ))
 }
-fn get_z_node_indices(periodicity: Periodicity, map: &CamelMap) -> Vec<u32> {
-    let mut res: Vec<u32> = vec![];
-    let mut node = periodicity.start_node;
-    std::iter::repeat(map.instruction.as_bytes())
-        .take(periodicity.period)
+
+/// Indices of the steps on a Z node when walking `passes` times over the
+/// instructions from `start`
+fn get_z_node_indices(start: usize, passes: usize, map: &CamelMap) -> Vec<usize> {
+    let mut res = vec![];
+    let mut node = start;
+    std::iter::repeat_n(map.instruction.as_bytes(), passes)
         .flatten()
         .enumerate()
         .for_each(|(idx, inst)| {
-            if node[2] == b'Z' {
-                res.push(idx.try_into().unwrap());
-            }
-            let branches = &map.mapping[&node];
-            node = match inst {
-                b'L' => branches.0,
-                b'R' => branches.1,
-                _ => panic!(),
+            if map.ends_with(node, b'Z') {
+                res.push(idx);
             }
+            node = map.step(node, *inst);
         });
     res
 }
 
+/// The steps at which a ghost is on a Z node
+#[derive(Debug, Clone)]
+struct ZSchedule {
+    /// steps before the ghost enters its cycle
+    prefix_steps: Vec<usize>,
+    /// steps during the first traversal of the cycle, repeating with `period`
+    cycle_steps: Vec<usize>,
+    period: usize,
+}
+
+impl ZSchedule {
+    fn new(periodicity: &Periodicity, start: usize, map: &CamelMap) -> Self {
+        let pass_len = map.instruction.len();
+        let cycle_start = periodicity.start_cycle * pass_len;
+        ZSchedule {
+            prefix_steps: get_z_node_indices(start, periodicity.start_cycle, map),
+            cycle_steps: get_z_node_indices(
+                periodicity.start_node,
+                periodicity.period,
+                map,
+            )
+            .into_iter()
+            .map(|idx| idx + cycle_start)
+            .collect(),
+            period: periodicity.period * pass_len,
+        }
+    }
+
+    fn is_z_step(&self, step: usize) -> bool {
+        self.prefix_steps.contains(&step)
+            || self
+                .cycle_steps
+                .iter()
+                .any(|&first| step >= first && (step - first).is_multiple_of(self.period))
+    }
+}
+
+/// First step at which all ghosts are on a Z node at the same time
+fn first_common_z_step(schedules: &[ZSchedule]) -> Option<usize> {
+    // a hit before some ghost entered its cycle is easiest checked directly
+    let prefix_hit = schedules
+        .iter()
+        .flat_map(|s| s.prefix_steps.iter().copied())
+        .filter(|&step| schedules.iter().all(|s| s.is_z_step(step)))
+        .min();
+
+    // otherwise every ghost is in one of its cycle residues
+    let cycle_hit = schedules
+        .iter()
+        .map(|s| s.cycle_steps.iter().map(move |&first| (first, s.period)))
+        .multi_cartesian_product()
+        .filter_map(|residues| {
+            let (a, m) = crt_all(
+                residues
+                    .iter()
+                    .map(|&(first, period)| (first as i128, period as i128)),
+            )?;
+            let earliest = residues.iter().map(|&(first, _)| first).max()? as i128;
+            let step = if a >= earliest {
+                a
+            } else {
+                a + (earliest - a + m - 1) / m * m
+            };
+            usize::try_from(step).ok()
+        })
+        .min();
+
+    prefix_hit.into_iter().chain(cycle_hit).min()
+}
+
+/// First step at which all ghosts are on a Z node at the same time, found by
+/// walking them in lockstep for at most `max_steps` steps
+fn lockstep_common_z_step(
+    map: &CamelMap,
+    starts: &[usize],
+    max_steps: usize,
+) -> Option<usize> {
+    let pass_len = map.instruction.len();
+    // offsets within a pass at which a ghost starting the pass on a node is on Z
+    let z_offsets = (0..map.names.len())
+        .map(|node| get_z_node_indices(node, 1, map))
+        .collect::<Vec<_>>();
+    let mut ghosts = starts.to_vec();
+    let mut pass_start = 0;
+    while pass_start < max_steps {
+        let (first, others) = ghosts.split_first()?;
+        if let Some(offset) = z_offsets[*first].iter().find(|offset| {
+            others
+                .iter()
+                .all(|&ghost| z_offsets[ghost].contains(offset))
+        }) {
+            return Some(pass_start + offset).filter(|&step| step < max_steps);
+        }
+        for ghost in &mut ghosts {
+            *ghost = map.pass[*ghost];
+        }
+        pass_start += pass_len;
+    }
+    None
+}
+
 fn main() {
     let input_file_path = env::args().nth(1).unwrap_or("08/input.txt".into());
     let input = fs::read_to_string(&input_file_path).unwrap();
     let map: CamelMap = input.as_str().try_into().unwrap();
 
-    let p1_steps = map
-        .instruction
-        .chars()
-        .cycle()
-        .fold_while((['A' as u8; 3], 0), |acc, inst| {
-            if !map.mapping.contains_key(&acc.0) {
-                return FoldWhile::Done((['Z' as u8; 3], 0));
-            }
-            let branches = &map.mapping[&acc.0];
-            let next = match inst {
-                'L' => branches.0,
-                'R' => branches.1,
-                _ => panic!(),
-            };
-            if next == ['Z' as u8; 3] {
-                FoldWhile::Done((next, acc.1 + 1))
-            } else {
-                FoldWhile::Continue((next, acc.1 + 1))
-            }
-        })
-        .into_inner()
-        .1;
-
-    println!("Number of steps for part 1: {p1_steps}");
+    let node_arg = |prefix: &str, default: &str| {
+        let name = env::args()
+            .skip(2)
+            .find_map(|a| a.strip_prefix(prefix).map(str::to_owned))
+            .unwrap_or(default.to_owned());
+        map.node(&name)
+    };
+    let p1_steps = node_arg("--start=", "AAA").and_then(|start| {
+        let goal = node_arg("--goal=", "ZZZ")?;
+        steps_to(&map, start, goal)
+    });
+    match p1_steps {
+        Ok(p1_steps) => println!("Number of steps for part 1: {p1_steps}"),
+        Err(e) => println!("No answer for part 1: {e}"),
+    }
 
-    let p2_starting_pos = map
-        .mapping
-        .keys()
-        .filter(|pos| pos[2] == 'A' as u8)
+    let p2_starting_pos = (0..map.names.len())
+        .filter(|&node| map.ends_with(node, b'A'))
         .collect::<Vec<_>>();
-    dbg!(&p2_starting_pos);
 
     let periodicities = p2_starting_pos
         .iter()
-        .map(|node| get_periodicity(&map, **node))
-        .map(|periodicity| (periodicity.clone(), get_z_node_indices(periodicity, &map)))
-        .map(|(periodicity, z_indices)| {
-            assert_eq!(z_indices.len(), 1); // always one with this input
-            let first_z_step = periodicity.start_cycle * map.instruction.len()
-                + *z_indices.first().unwrap() as usize;
-            let z_step_period = periodicity.period * map.instruction.len();
-            assert_eq!(first_z_step, z_step_period); // input seems designed so
-            first_z_step
-        })
+        .map(|&node| get_periodicity(&map, node))
         .collect::<Vec<_>>();
-    dbg!(&periodicities);
-
-    let p2_steps = periodicities
+    let schedules = p2_starting_pos
         .iter()
-        .fold(1, |acc, &p| num::integer::lcm(acc, p));
+        .zip(&periodicities)
+        .map(|(&node, periodicity)| ZSchedule::new(periodicity, node, &map))
+        .collect::<Vec<_>>();
+
+    if let Some(path) = env::args()
+        .skip(2)
+        .find_map(|a| a.strip_prefix("--dot=").map(str::to_owned))
+    {
+        let cycles = if env::args().skip(2).any(|a| a == "--dot-cycles") {
+            periodicities.as_slice()
+        } else {
+            &[]
+        };
+        fs::write(&path, dot::to_dot(&map, cycles)).unwrap();
+    }
+
+    let p2_steps = first_common_z_step(&schedules);
+    match p2_steps {
+        Some(p2_steps) => println!("Number of steps for part 2: {p2_steps}"),
+        None => println!("Number of steps for part 2: never"),
+    }
 
-    println!("Number of steps for part 2: {p2_steps}");
+    if let Some(max_steps) = env::args()
+        .skip(2)
+        .find_map(|a| a.strip_prefix("--brute-force=").map(str::to_owned))
+    {
+        let max_steps = max_steps
+            .parse()
+            .expect("expecting --brute-force=MAX_STEPS");
+        let brute = lockstep_common_z_step(&map, &p2_starting_pos, max_steps);
+        match brute {
+            Some(steps) => println!("Lockstep simulation: {steps}"),
+            None => println!("Lockstep simulation: none within {max_steps} steps"),
+        }
+        if p2_steps.is_some_and(|steps| steps < max_steps) && brute != p2_steps {
+            println!("Mismatch with the CRT answer!");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(
+        prefix_steps: &[usize],
+        cycle_steps: &[usize],
+        period: usize,
+    ) -> ZSchedule {
+        ZSchedule {
+            prefix_steps: prefix_steps.to_vec(),
+            cycle_steps: cycle_steps.to_vec(),
+            period,
+        }
+    }
+
+    #[test]
+    fn walks_to_goal() {
+        let input = fs::read_to_string("example_input_1.txt").unwrap();
+        let map: CamelMap = input.as_str().try_into().unwrap();
+        let node = |name| map.node(name).unwrap();
+        assert_eq!(steps_to(&map, node("AAA"), node("ZZZ")), Ok(2));
+        assert_eq!(steps_to(&map, node("AAA"), node("AAA")), Ok(0));
+        assert_eq!(steps_to(&map, node("BBB"), node("EEE")), Ok(1));
+        assert_eq!(
+            steps_to(&map, node("AAA"), node("BBB")),
+            Err(
+                "BBB is unreachable from AAA, which loops every 2 steps after 2 steps"
+                    .to_owned()
+            )
+        );
+        assert_eq!(map.node("QQQ"), Err("unknown node QQQ".to_owned()));
+
+        let input = fs::read_to_string("example_input_2.txt").unwrap();
+        let map: CamelMap = input.as_str().try_into().unwrap();
+        assert_eq!(
+            steps_to(&map, map.node("AAA").unwrap(), map.node("ZZZ").unwrap()),
+            Ok(6)
+        );
+    }
+
+    #[test]
+    fn validates_input() {
+        let parse = |input: &str| CamelMap::try_from(input).map(|_| ());
+        assert_eq!(parse("LR\n\nAAA = (AAA, AAA)\n"), Ok(()));
+        assert_eq!(
+            parse("LXR\n\nAAA = (AAA, AAA)\n"),
+            Err("invalid instruction 'X' at position 2, expecting L or R".to_owned())
+        );
+        assert_eq!(
+            parse("\n\nAAA = (AAA, AAA)\n"),
+            Err("expecting at least one instruction".to_owned())
+        );
+        assert_eq!(
+            parse("L\n\nAAA = (AAA, BBB)\nCCC = (BBB, DDD)\n"),
+            Err("node BBB referenced on line 3 is never defined".to_owned())
+        );
+        assert_eq!(
+            parse("L\n\nAAA = (AAA, AAA)\nAAA = (AAA, AAA)\n"),
+            Err("node AAA defined again on line 4".to_owned())
+        );
+        assert_eq!(
+            parse("L\n\nAAA = (AAA, AAA)\nAAAA = (AAA, AAA)\n"),
+            Err(
+                "expecting 'XXX = (YYY, ZZZ)' on line 4, got 'AAAA = (AAA, AAA)'"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn finds_first_common_z_step() {
+        // the usual input shape, a single Z at the end of each cycle
+        assert_eq!(
+            first_common_z_step(&[schedule(&[], &[4], 4), schedule(&[], &[6], 6)]),
+            Some(12)
+        );
+        // offsets and non-coprime periods
+        assert_eq!(
+            first_common_z_step(&[schedule(&[], &[6], 4), schedule(&[], &[4], 6)]),
+            Some(10)
+        );
+        // several Z nodes per cycle
+        assert_eq!(
+            first_common_z_step(&[schedule(&[], &[5, 7], 10), schedule(&[], &[3], 4)]),
+            Some(7)
+        );
+        // a Z node only passed before entering the cycle
+        assert_eq!(
+            first_common_z_step(&[schedule(&[2], &[9], 4), schedule(&[], &[2], 1)]),
+            Some(2)
+        );
+        // never in sync
+        assert_eq!(
+            first_common_z_step(&[schedule(&[], &[1], 4), schedule(&[], &[2], 6)]),
+            None
+        );
+    }
+
+    #[test]
+    fn solves_example() {
+        let input = fs::read_to_string("example_input_3.txt").unwrap();
+        let map: CamelMap = input.as_str().try_into().unwrap();
+        let starts = [map.index[b"11A"], map.index[b"22A"]];
+        let schedules =
+            starts.map(|node| ZSchedule::new(&get_periodicity(&map, node), node, &map));
+        assert_eq!(schedules[1].cycle_steps, [3, 6]);
+        assert_eq!(first_common_z_step(&schedules), Some(6));
+        assert_eq!(lockstep_common_z_step(&map, &starts, 100), Some(6));
+        assert_eq!(lockstep_common_z_step(&map, &starts, 6), None);
+    }
+
+    #[test]
+    fn lockstep_agrees_with_crt_on_random_maps() {
+        // xorshift, good enough to make up small maps
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut rand = |n: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as usize % n
+        };
+        for _ in 0..100 {
+            let n_nodes = 2 + rand(10);
+            let name = |idx: usize| {
+                let last = match idx % 3 {
+                    0 => 'A',
+                    1 => 'Z',
+                    _ => 'X',
+                };
+                format!("{}{}{last}", (b'A' + idx as u8 / 10) as char, idx % 10)
+            };
+            let instruction = (0..1 + rand(5))
+                .map(|_| if rand(2) == 0 { 'L' } else { 'R' })
+                .collect::<String>();
+            let lines = (0..n_nodes)
+                .map(|idx| {
+                    let (l, r) = (rand(n_nodes), rand(n_nodes));
+                    format!("{} = ({}, {})", name(idx), name(l), name(r))
+                })
+                .join("\n");
+            let map: CamelMap = format!("{instruction}\n\n{lines}")
+                .as_str()
+                .try_into()
+                .unwrap();
+
+            let starts = (0..n_nodes)
+                .filter(|&node| map.ends_with(node, b'A'))
+                .collect::<Vec<_>>();
+            let periodicities = starts
+                .iter()
+                .map(|&node| get_periodicity(&map, node))
+                .collect::<Vec<_>>();
+            let schedules = starts
+                .iter()
+                .zip(&periodicities)
+                .map(|(&node, p)| ZSchedule::new(p, node, &map))
+                .collect::<Vec<_>>();
+            // after every ghost entered its cycle the whole system repeats with
+            // the lcm of the periods
+            let max_steps = periodicities
+                .iter()
+                .map(|p| (p.start_cycle + p.period) * instruction.len())
+                .max()
+                .unwrap()
+                + schedules.iter().map(|s| s.period).fold(1, num_lcm);
+            assert_eq!(
+                lockstep_common_z_step(&map, &starts, max_steps),
+                first_common_z_step(&schedules),
+                "{instruction}\n\n{lines}"
+            );
+        }
+    }
+
+    fn num_lcm(a: usize, b: usize) -> usize {
+        common::math::lcm(a as i128, b as i128) as usize
+    }
 }
@@ -1,5 +1,9 @@
+mod polynomial;
+
 use std::{env, fs};
 
+use polynomial::Polynomial;
+
 fn main() {
     let input_file_path = env::args().nth(1).unwrap_or("09/example_input.txt".into());
     let input = fs::read_to_string(&input_file_path).unwrap();
@@ -7,56 +11,102 @@ fn main() {
         .lines()
         .map(|l| {
             l.split_whitespace()
-                .map(str::parse::<i32>)
+                .map(str::parse::<i128>)
                 .collect::<Result<Vec<_>, _>>()
         })
         .collect::<Result<Vec<_>, _>>()
         .unwrap();
-    let predictions = lines.iter().map(predict_digit).collect::<Vec<_>>();
-    let p1_answer: i32 = predictions.iter().map(|x| x.1).sum();
+    let polynomials = fit_lines(&lines).unwrap();
+    let max_degree = polynomials
+        .iter()
+        .map(Polynomial::degree)
+        .max()
+        .unwrap_or(0);
+    println!("Highest polynomial degree: {max_degree}");
+
+    let p1_answer = sum_predictions(&lines, &polynomials, 1).unwrap();
     println!("The sum of predictions for part 1 is {p1_answer}");
-    let p2_answer: i32 = predictions.iter().map(|x| x.0).sum();
+    let p2_answer = sum_predictions(&lines, &polynomials, -1).unwrap();
     println!("The sum of predictions for part 2 is {p2_answer}");
-}
 
-fn predict_digit(line: &Vec<i32>) -> (i32, i32) {
-    let mut diff_sequences = vec![line.clone()];
-    while !diff_sequences.last().unwrap().iter().all(|&x| x == 0) {
-        let last = diff_sequences.last().unwrap().iter().cloned();
-        diff_sequences.push(last.clone().zip(last.skip(1)).map(|(a, b)| b - a).collect());
+    if let Some(steps) = env::args()
+        .skip(2)
+        .find_map(|a| a.strip_prefix("--steps=").map(str::to_owned))
+    {
+        let steps: i128 = steps.parse().expect("expecting --steps=K");
+        for steps in [steps, -steps] {
+            match sum_predictions(&lines, &polynomials, steps) {
+                Some(sum) => {
+                    println!("The sum of predictions {steps} steps out is {sum}")
+                }
+                None => println!("The sum of predictions {steps} steps out overflows"),
+            }
+        }
     }
-    // 0   3   6   9  12  15   _18_
-    //   3   3   3   3   3   _3_
-    //     0   0   0   0   0
-    let next_pred = diff_sequences
+}
+
+fn fit_lines(lines: &[Vec<i128>]) -> Result<Vec<Polynomial>, String> {
+    lines
         .iter()
-        .map(|seq| seq.last().unwrap_or(&0))
-        .sum();
-    // _5_  10  13  16  21  30  45
-    //   _5_   3   3   5   9  15
-    //    _-2_   0   2   4   6
-    //       _2_   2   2   2
-    //          0   0   0
-    let prev_pred = diff_sequences
+        .enumerate()
+        .map(|(idx, line)| {
+            Polynomial::fit(line).map_err(|e| format!("line {}: {e}", idx + 1))
+        })
+        .collect()
+}
+
+/// Sum of the values `steps` after the end of each line, or before its start
+/// for negative `steps`
+fn sum_predictions(
+    lines: &[Vec<i128>],
+    polynomials: &[Polynomial],
+    steps: i128,
+) -> Option<i128> {
+    lines
         .iter()
-        .map(|seq| seq.first().unwrap_or(&0))
-        .rfold(0i32, |acc, x| x - acc);
-    (prev_pred, next_pred)
+        .zip(polynomials)
+        .try_fold(0i128, |sum, (line, poly)| {
+            let x = if steps < 0 {
+                steps
+            } else {
+                line.len() as i128 - 1 + steps
+            };
+            sum.checked_add(poly.eval(x)?)
+        })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn predict(line: &[i128], steps: i128) -> i128 {
+        let lines = [line.to_vec()];
+        sum_predictions(&lines, &fit_lines(&lines).unwrap(), steps).unwrap()
+    }
+
     #[test]
     fn predict_next_digits() {
-        assert_eq!(predict_digit(&vec![0, 3, 6, 9, 12, 15]).1, 18);
-        assert_eq!(predict_digit(&vec![1, 3, 6, 10, 15, 21]).1, 28);
+        assert_eq!(predict(&[0, 3, 6, 9, 12, 15], 1), 18);
+        assert_eq!(predict(&[1, 3, 6, 10, 15, 21], 1), 28);
+        assert_eq!(predict(&[1, 3, 6, 10, 15, 21], 3), 45);
     }
 
     #[test]
     fn predict_previous_digits() {
-        assert_eq!(predict_digit(&vec![0, 3, 6, 9, 12, 15]).0, -3);
-        assert_eq!(predict_digit(&vec![1, 3, 6, 10, 15, 21]).0, 0);
+        assert_eq!(predict(&[0, 3, 6, 9, 12, 15], -1), -3);
+        assert_eq!(predict(&[1, 3, 6, 10, 15, 21], -1), 0);
+        assert_eq!(predict(&[10, 13, 16, 21, 30, 45], -1), 5);
+    }
+
+    #[test]
+    fn reports_line_of_non_polynomial() {
+        let lines = [vec![0, 3, 6], vec![1, 3, 6, 10], vec![1, 2, 4, 8]];
+        assert_eq!(
+            fit_lines(&lines),
+            Err(
+                "line 3: no constant difference row in 4 values, not a polynomial"
+                    .to_owned()
+            )
+        );
     }
 }
@@ -0,0 +1,99 @@
+/// A polynomial given by its Newton forward differences at index 0, so that
+/// `f(x) = sum over k of differences[k] * binomial(x, k)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polynomial {
+    differences: Vec<i128>,
+}
+
+impl Polynomial {
+    /// The polynomial of lowest degree through `(0, values[0]), (1, values[1]), ...`
+    ///
+    /// Errors unless some difference row is constant over at least two values,
+    /// as otherwise any next value would fit.
+    pub fn fit(values: &[i128]) -> Result<Self, String> {
+        if values.len() < 2 {
+            return Err(format!("need at least two values, got {}", values.len()));
+        }
+        let mut differences = vec![];
+        let mut row = values.to_vec();
+        while row.iter().any(|&x| x != 0) {
+            if row.len() < 2 {
+                return Err(format!(
+                    "no constant difference row in {} values, not a polynomial",
+                    values.len()
+                ));
+            }
+            differences.push(row[0]);
+            row = row.windows(2).map(|w| w[1] - w[0]).collect();
+        }
+        Ok(Polynomial { differences })
+    }
+
+    /// Degree of the polynomial, 0 for constants including zero
+    pub fn degree(&self) -> usize {
+        self.differences.len().saturating_sub(1)
+    }
+
+    /// Value at index `x`, which can be negative or beyond the fitted values,
+    /// `None` on overflow
+    pub fn eval(&self, x: i128) -> Option<i128> {
+        let mut res: i128 = 0;
+        // binomial(x, k) for any integer x, the division is always exact
+        let mut binomial: i128 = 1;
+        for (k, &diff) in self.differences.iter().enumerate() {
+            res = res.checked_add(diff.checked_mul(binomial)?)?;
+            let k = k as i128;
+            binomial = binomial.checked_mul(x - k)? / (k + 1);
+        }
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extrapolates_both_ways() {
+        let quadratic = Polynomial::fit(&[1, 3, 6, 10, 15, 21]).unwrap();
+        assert_eq!(quadratic.degree(), 2);
+        assert_eq!(quadratic.eval(6), Some(28));
+        assert_eq!(quadratic.eval(-1), Some(0));
+        assert_eq!(quadratic.eval(-3), Some(1));
+        assert_eq!(quadratic.eval(100), Some(101 * 102 / 2));
+
+        assert_eq!(
+            Polynomial::fit(&[10, 13, 16, 21, 30, 45]).unwrap().eval(-1),
+            Some(5)
+        );
+        assert_eq!(Polynomial::fit(&[7, 7, 7]).unwrap().degree(), 0);
+        assert_eq!(Polynomial::fit(&[0, 0]).unwrap().eval(5), Some(0));
+
+        // x^5 - 3x, evaluated far enough out to overflow an i64
+        let f = |x: i128| x.pow(5) - 3 * x;
+        let quintic = Polynomial::fit(&(-2..10).map(f).collect::<Vec<_>>()).unwrap();
+        assert_eq!(quintic.degree(), 5);
+        for x in [-1_000_000, -7, 0, 3, 12, 5_000_000] {
+            assert_eq!(quintic.eval(x + 2), Some(f(x)), "x = {x}");
+        }
+        assert_eq!(quintic.eval(1 << 30), None);
+    }
+
+    #[test]
+    fn rejects_non_polynomials() {
+        let err =
+            Err("no constant difference row in 6 values, not a polynomial".to_owned());
+        assert_eq!(Polynomial::fit(&[1, 2, 4, 8, 16, 32]), err);
+        // degree 5 needs 7 values to see its constant difference repeat
+        let quintic = (0..7).map(|x: i128| x.pow(5)).collect::<Vec<_>>();
+        assert!(Polynomial::fit(&quintic[..6]).is_err());
+        assert_eq!(Polynomial::fit(&quintic).unwrap().degree(), 5);
+        assert!(Polynomial::fit(&[3, 5, 7]).is_ok());
+        assert!(Polynomial::fit(&[3, 5]).is_err());
+        assert_eq!(Polynomial::fit(&[3, 3]).unwrap().degree(), 0);
+        assert_eq!(
+            Polynomial::fit(&[0]),
+            Err("need at least two values, got 1".to_owned())
+        );
+    }
+}
@@ -1,32 +1,40 @@
+mod pipe;
 mod tubemap;
 
-use common::twod::{Direction as Dir, Point};
-use itertools::Itertools;
-use std::{
-    collections::{HashMap, HashSet},
-    env, fs,
+use common::{
+    recorder::Recorder,
+    twod::{Direction as Dir, Grid, Point},
+    viewer::Viewer,
 };
+use itertools::Itertools;
+use std::{collections::HashSet, env, fs, time::Duration};
 
-use crate::tubemap::TubeMap;
+use crate::{pipe::Pipe, tubemap::TubeMap};
 
 fn main() {
     let input_file_path = env::args().nth(1).unwrap_or("10/example_input.txt".into());
     let input = fs::read_to_string(&input_file_path).unwrap();
     let map: TubeMap = input.parse().unwrap();
-    let pipe_ends = HashMap::<_, _>::from([
-        (b'|', vec![Dir::N, Dir::S]),
-        (b'-', vec![Dir::W, Dir::E]),
-        (b'L', vec![Dir::N, Dir::E]),
-        (b'J', vec![Dir::W, Dir::N]),
-        (b'7', vec![Dir::W, Dir::S]),
-        (b'F', vec![Dir::E, Dir::S]),
-        (b'.', vec![]),
-        (b'S', vec![Dir::E, Dir::S, Dir::W, Dir::N]),
-    ]);
+    let record_path = env::args()
+        .skip(2)
+        .find_map(|a| a.strip_prefix("--record=").map(str::to_owned));
+    let replay = env::args().skip(2).any(|a| a == "--replay");
+    let mut recorder = if record_path.is_some() || replay {
+        // aim for a few hundred frames at most
+        Recorder::new().every(map.grid.data.len() / 200)
+    } else {
+        Recorder::disabled()
+    };
 
     // crawl around animal's path to get len
-    let animal_path = try_crawl_path(&map, map.start, &pipe_ends)
+    let animal_path = try_crawl_path(&map, map.start, &mut recorder)
         .expect("Failed to crawl the animal's path");
+    if let Some(record_path) = record_path {
+        write_recording(&recorder, &record_path);
+    }
+    if replay {
+        recorder.replay(Duration::from_millis(50)).unwrap();
+    }
     println!(
         "Len is {}, halfway point is {}, {} steps away from start",
         animal_path.len(),
@@ -34,69 +42,90 @@ fn main() {
         animal_path.len() / 2
     );
 
+    if env::args().skip(2).any(|a| a == "--runs") {
+        print_pipe_runs(&map);
+    }
+
     // find the enclosed tiles for part 2
-    let mut map = map.clone();
     let floodfill = floodfill_path(&animal_path, &map);
 
-    // it turns out finding inner paths is not the point, everything in the floodfill
-    // counts as enclosed
-    //
-    // for p in &floodfill {
-    //     match map[*p] {
-    //         b'.' | b'X' => continue,
-    //         _ => (),
-    //     }
-    //     if let Some(path) = try_crawl_path(&map, *p, &pipe_ends) {
-    //         for inner_path_content in floodfill_path(&path, &map) {
-    //             map[inner_path_content] = b'X';
-    //         }
-    //         for inner_path_seg in path {
-    //             // to prevent us from crawling this path again
-    //             map[inner_path_seg] = b'X';
-    //         }
-    //     }
-    // }
-    // let n_tiles = floodfill.iter().filter(|&&p| map[p] != b'X').count();
-
-    let n_tiles = floodfill.len();
+    let method = env::args()
+        .skip(2)
+        .find_map(|a| a.strip_prefix("--method=").map(str::to_owned))
+        .unwrap_or("floodfill".to_owned());
+    let n_tiles = match method.as_str() {
+        "floodfill" => floodfill.len(),
+        "scanline" => map.enclosed_by_scanline(&animal_path).len(),
+        "shoelace" => shoelace_enclosed(&animal_path),
+        "upscale" => map.enclosed_by_upscaling(&animal_path).len(),
+        _ => panic!("expecting --method=floodfill, scanline, shoelace or upscale"),
+    };
     println!("There are {n_tiles} tiles enclosed by the loop");
 
+    if env::args().skip(2).any(|a| a == "--view") {
+        Viewer::new(&map.grid, Pipe::glyph)
+            .with_cursor(map.start)
+            .with_overlay("loop", animal_path.iter().copied())
+            .with_overlay("enclosed", floodfill.iter().copied())
+            .run()
+            .unwrap();
+    }
+
     // print the map as we see it
-    (0..map.grid.width())
-        .cartesian_product(0..map.grid.height())
-        .map(|(x, y)| Point {
-            x: x as i32,
-            y: y as i32,
+    let on_loop = animal_path.iter().copied().collect::<HashSet<_>>();
+    let seen = (0..map.grid.height() as i32)
+        .map(|y| {
+            (0..map.grid.width() as i32)
+                .map(|x| {
+                    let p = Point { x, y };
+                    if on_loop.contains(&p) {
+                        map[p].glyph()
+                    } else if floodfill.contains(&p) {
+                        '.'
+                    } else {
+                        'x'
+                    }
+                })
+                .collect::<String>()
         })
-        .for_each(|p| {
-            if animal_path.contains(&p) {
-                //
-            } else if floodfill.contains(&p) {
-                map[p] = b'.';
-            } else {
-                map[p] = b'x';
-            }
-        });
-    println!("map:\n{}", map.to_string());
+        .join("\n");
+    println!("map:\n{seen}");
 }
 
 fn try_crawl_path(
     map: &TubeMap,
     start: Point,
-    pipe_ends: &HashMap<u8, Vec<Dir>>,
+    recorder: &mut Recorder,
 ) -> Option<Vec<Point>> {
     let mut origin_dir = Dir::S;
     let mut path = vec![];
     let mut p = start;
-    while path.len() < 1_000_000 {
+    let mut visited = Grid {
+        data: vec![false; map.grid.data.len()],
+        width: map.grid.width(),
+    };
+    // every pipe connects at most two neighbours, so we either come back to
+    // the start or hit a dead end
+    loop {
         (origin_dir, p) = map
             .neighbours(p)
             .filter(|(dir, _)| dir.opposite() != origin_dir)
-            .filter(|(dir, _)| pipe_ends[&map[p]].contains(dir))
-            .find(|(dir, neighbour)| {
-                pipe_ends[&map[*neighbour]].contains(&dir.opposite())
-            })?;
+            .filter(|&(dir, _)| map[p].connects(dir))
+            .find(|&(dir, neighbour)| map[neighbour].connects(dir.opposite()))?;
         path.push(p);
+        if recorder.is_enabled() {
+            visited[p] = true;
+            recorder.push_grid(
+                &map.grid,
+                |q, tile| match q {
+                    q if q == p => '@',
+                    q if q == start => 'S',
+                    q if visited[q] => tile.glyph(),
+                    _ => ' ',
+                },
+                format!("step {}, at {p}", path.len()),
+            );
+        }
         if p == start {
             break;
         }
@@ -104,7 +133,53 @@ fn try_crawl_path(
     Some(path)
 }
 
-fn ensure_clockwise_path(path: &Vec<Point>) -> Vec<Point> {
+fn print_pipe_runs(map: &TubeMap) {
+    let runs = map.pipe_runs();
+    let n_loops = runs.iter().filter(|r| r.closed).count();
+    println!(
+        "{n_loops} closed loops and {} open chains of pipes",
+        runs.len() - n_loops
+    );
+    for run in runs
+        .iter()
+        .sorted_by_key(|r| (!r.closed, std::cmp::Reverse(r.len())))
+    {
+        let (top_left, bottom_right) = run.bounding_box();
+        println!(
+            "{} of {} tiles from {} to {}, starting at {}{}",
+            if run.closed { "loop" } else { "chain" },
+            run.len(),
+            top_left,
+            bottom_right,
+            run.tiles[0],
+            if run.tiles.contains(&map.start) {
+                ", the animal's"
+            } else {
+                ""
+            }
+        );
+    }
+}
+
+fn write_recording(recorder: &Recorder, path: &str) {
+    let file = fs::File::create(path).unwrap();
+    if path.ends_with(".gif") {
+        recorder
+            .write_gif(file, 4, Duration::from_millis(50), |c| match c {
+                '@' => [255, 0, 0],
+                'S' => [0, 255, 0],
+                ' ' => [0, 0, 0],
+                _ => [255, 255, 255],
+            })
+            .unwrap();
+    } else {
+        recorder
+            .write_text(&mut std::io::BufWriter::new(file))
+            .unwrap();
+    }
+}
+
+fn ensure_clockwise_path(path: &[Point]) -> Vec<Point> {
     if path.is_empty() {
         return vec![];
     }
@@ -115,23 +190,20 @@ fn ensure_clockwise_path(path: &Vec<Point>) -> Vec<Point> {
         .filter(|(_, p)| p.y == miny)
         .min_by(|a, b| a.1.x.cmp(&b.1.x))
         .unwrap();
-    let next = path
-        .iter()
-        .nth(topleft_pos + 1)
-        .unwrap_or(path.first().unwrap());
+    let next = path.get(topleft_pos + 1).unwrap_or(path.first().unwrap());
     if next.x - topleft.x == 1 {
-        path.clone()
+        path.to_vec()
     } else {
         path.iter().cloned().rev().collect()
     }
 }
 
-fn floodfill_path(path: &Vec<Point>, map: &TubeMap) -> HashSet<Point> {
-    let animal_path = ensure_clockwise_path(&path);
+fn floodfill_path(path: &[Point], map: &TubeMap) -> HashSet<Point> {
+    let animal_path = ensure_clockwise_path(path);
     let mut floodfill = HashSet::new();
     for (a, b) in animal_path.iter().zip(animal_path.iter().cycle().skip(1)) {
         let dir = (b.x - a.x, b.y - a.y);
-        let dir_rot90 = (-1 * dir.1, dir.0);
+        let dir_rot90 = (-dir.1, dir.0);
         for s in [a, b] {
             let floodfill_from_here = map.grid.get_floodfill_region(
                 Point {
@@ -145,3 +217,42 @@ fn floodfill_path(path: &Vec<Point>, map: &TubeMap) -> HashSet<Point> {
     }
     floodfill
 }
+
+/// Number of tiles enclosed by the loop `path`, from its area by the shoelace
+/// formula and Pick's theorem `area = inside + boundary / 2 - 1`
+fn shoelace_enclosed(path: &[Point]) -> usize {
+    let twice_area = path
+        .iter()
+        .zip(path.iter().cycle().skip(1))
+        .map(|(a, b)| a.x as i64 * b.y as i64 - b.x as i64 * a.y as i64)
+        .sum::<i64>()
+        .unsigned_abs() as usize;
+    (twice_area + 2 - path.len()) / 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enclosed_tile_methods_agree() {
+        for (file, expected) in [
+            ("example_input.txt", 1),
+            ("test_input_1.txt", 2),
+            ("test_input_2.txt", 4),
+            ("test_input_3.txt", 8),
+            ("test_input_4.txt", 10),
+            // test_input_5.txt with its invalid tile replaced by ground
+            ("test_input_6.txt", 3),
+        ] {
+            let map: TubeMap = fs::read_to_string(file).unwrap().parse().unwrap();
+            let path =
+                try_crawl_path(&map, map.start, &mut Recorder::disabled()).unwrap();
+            let floodfill = floodfill_path(&path, &map);
+            assert_eq!(floodfill.len(), expected, "{file}");
+            assert_eq!(map.enclosed_by_scanline(&path), floodfill, "{file}");
+            assert_eq!(map.enclosed_by_upscaling(&path), floodfill, "{file}");
+            assert_eq!(shoelace_enclosed(&path), expected, "{file}");
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use common::twod::Direction as Dir;
+
+/// A tile of the map, named after the directions it connects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pipe {
+    Ground,
+    NorthSouth,
+    EastWest,
+    NorthEast,
+    NorthWest,
+    SouthWest,
+    SouthEast,
+}
+
+impl Pipe {
+    pub const PIPES: [Pipe; 6] = [
+        Pipe::NorthSouth,
+        Pipe::EastWest,
+        Pipe::NorthEast,
+        Pipe::NorthWest,
+        Pipe::SouthWest,
+        Pipe::SouthEast,
+    ];
+
+    /// The directions the pipe connects to, none for ground
+    pub fn ends(self) -> &'static [Dir] {
+        match self {
+            Pipe::Ground => &[],
+            Pipe::NorthSouth => &[Dir::N, Dir::S],
+            Pipe::EastWest => &[Dir::E, Dir::W],
+            Pipe::NorthEast => &[Dir::N, Dir::E],
+            Pipe::NorthWest => &[Dir::N, Dir::W],
+            Pipe::SouthWest => &[Dir::S, Dir::W],
+            Pipe::SouthEast => &[Dir::E, Dir::S],
+        }
+    }
+
+    pub fn connects(self, dir: Dir) -> bool {
+        self.ends().contains(&dir)
+    }
+
+    /// The pipe connecting exactly the given directions
+    pub fn from_ends(ends: &[Dir]) -> Option<Pipe> {
+        Pipe::PIPES
+            .into_iter()
+            .find(|pipe| ends.len() == 2 && ends.iter().all(|&dir| pipe.connects(dir)))
+    }
+
+    /// The character used in the puzzle input
+    pub fn byte(self) -> u8 {
+        match self {
+            Pipe::Ground => b'.',
+            Pipe::NorthSouth => b'|',
+            Pipe::EastWest => b'-',
+            Pipe::NorthEast => b'L',
+            Pipe::NorthWest => b'J',
+            Pipe::SouthWest => b'7',
+            Pipe::SouthEast => b'F',
+        }
+    }
+
+    /// Box-drawing representation
+    pub fn glyph(self) -> char {
+        match self {
+            Pipe::Ground => '.',
+            Pipe::NorthSouth => '│',
+            Pipe::EastWest => '─',
+            Pipe::NorthEast => '└',
+            Pipe::NorthWest => '┘',
+            Pipe::SouthWest => '┐',
+            Pipe::SouthEast => '┌',
+        }
+    }
+}
+
+impl TryFrom<u8> for Pipe {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        std::iter::once(Pipe::Ground)
+            .chain(Pipe::PIPES)
+            .find(|pipe| pipe.byte() == value)
+            .ok_or(format!("invalid tile '{}'", value.escape_ascii()))
+    }
+}
+
+impl std::fmt::Display for Pipe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}", self.glyph()))
+    }
+}
@@ -2,15 +2,19 @@ use common::twod::Grid;
 use common::twod::Point;
 use common::twod::PointNeighbours;
 
+use std::collections::HashSet;
 use std::ops::Index;
 use std::ops::IndexMut;
 use std::str::FromStr;
 
 use itertools::Itertools;
 
+use crate::pipe::Pipe;
+
 #[derive(Debug, Clone)]
 pub struct TubeMap {
-    pub grid: Grid<u8>,
+    /// the tiles, with the pipe under the start inferred in place of `S`
+    pub grid: Grid<Pipe>,
     pub start: Point,
 }
 
@@ -18,23 +22,40 @@ impl FromStr for TubeMap {
     type Err = String;
     fn from_str(value: &str) -> Result<TubeMap, String> {
         let width = value.lines().next().map(|l| l.len()).unwrap_or(0);
-        let map = value
-            .as_bytes()
-            .iter()
-            .cloned()
-            .filter(|&c| c != b'\n')
-            .collect::<Vec<_>>();
-        let start_idx_in_raw = map
-            .iter()
-            .position(|&c| c == b'S')
-            .ok_or("Could not find start")?;
-        Ok(TubeMap {
-            grid: Grid { data: map, width },
-            start: Point {
-                x: (start_idx_in_raw as usize % width).try_into().unwrap(),
-                y: (start_idx_in_raw as usize / width).try_into().unwrap(),
-            },
-        })
+        let mut data = vec![];
+        let mut start = None;
+        for (y, line) in value.lines().enumerate() {
+            if line.len() != width {
+                return Err(format!(
+                    "line {} is {} tiles wide, expecting {width}",
+                    y + 1,
+                    line.len()
+                ));
+            }
+            for (x, tile) in line.bytes().enumerate() {
+                let p = Point {
+                    x: x as i32,
+                    y: y as i32,
+                };
+                if tile == b'S' {
+                    if start.is_some() {
+                        return Err(format!("second start at {p}"));
+                    }
+                    start = Some(p);
+                    // replaced once the neighbours are known
+                    data.push(Pipe::Ground);
+                } else {
+                    data.push(Pipe::try_from(tile).map_err(|e| format!("{e} at {p}"))?);
+                }
+            }
+        }
+        let mut map = TubeMap {
+            grid: Grid { data, width },
+            start: start.ok_or("Could not find start")?,
+        };
+        let start_pipe = map.start_pipe()?;
+        map.grid[map.start] = start_pipe;
+        Ok(map)
     }
 }
 
@@ -44,20 +65,10 @@ impl From<&TubeMap> for String {
             .grid
             .data
             .iter()
-            .map(|&c| c as char)
-            .map(|c| match c {
-                '|' => '│',
-                '-' => '─',
-                'L' => '└',
-                'J' => '┘',
-                '7' => '┐',
-                'F' => '┌',
-                c => c,
-            })
+            .map(|pipe| pipe.glyph())
             .chunks(value.grid.width())
             .into_iter()
-            .map(|chunk| chunk.chain("\n".chars()))
-            .flatten()
+            .flat_map(|chunk| chunk.chain("\n".chars()))
             .collect()
     }
 }
@@ -69,7 +80,7 @@ impl std::fmt::Display for TubeMap {
 }
 
 impl Index<Point> for TubeMap {
-    type Output = u8;
+    type Output = Pipe;
 
     fn index(&self, index: Point) -> &Self::Output {
         &self.grid[index]
@@ -77,13 +88,352 @@ impl Index<Point> for TubeMap {
 }
 
 impl IndexMut<Point> for TubeMap {
-    fn index_mut(&mut self, index: Point) -> &mut u8 {
+    fn index_mut(&mut self, index: Point) -> &mut Pipe {
         &mut self.grid[index]
     }
 }
 
+/// A connected run of pipes, either a closed loop or an open chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipeRun {
+    /// the tiles in the order they connect, from one end for a chain
+    pub tiles: Vec<Point>,
+    pub closed: bool,
+}
+
+impl PipeRun {
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Top left and bottom right corners
+    pub fn bounding_box(&self) -> (Point, Point) {
+        let xs = self.tiles.iter().map(|p| p.x);
+        let ys = self.tiles.iter().map(|p| p.y);
+        (
+            Point {
+                x: xs.clone().min().unwrap(),
+                y: ys.clone().min().unwrap(),
+            },
+            Point {
+                x: xs.max().unwrap(),
+                y: ys.max().unwrap(),
+            },
+        )
+    }
+}
+
 impl TubeMap {
     pub fn neighbours(&self, p: Point) -> PointNeighbours {
         p.neighbours(self.grid.width() as i32, self.grid.height() as i32)
     }
+
+    /// Neighbours connected to `p` by pipes on both sides
+    fn linked(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        self.neighbours(p)
+            .filter(move |&(dir, q)| {
+                self[p].connects(dir) && self[q].connects(dir.opposite())
+            })
+            .map(|(_, q)| q)
+    }
+
+    /// Walk from `from` away from `prev` until a dead end or back at `from`,
+    /// returning the tiles passed and whether we came back
+    fn walk(&self, from: Point, mut prev: Option<Point>) -> (Vec<Point>, bool) {
+        let mut tiles = vec![];
+        let mut p = from;
+        while let Some(next) = self.linked(p).find(|&q| Some(q) != prev) {
+            if next == from {
+                return (tiles, true);
+            }
+            tiles.push(next);
+            (prev, p) = (Some(p), next);
+        }
+        (tiles, false)
+    }
+
+    /// Every loop and chain of pipes in the map, not only the one through the
+    /// start, in the order their first tile appears in the grid
+    pub fn pipe_runs(&self) -> Vec<PipeRun> {
+        let mut seen = HashSet::new();
+        let mut runs = vec![];
+        for y in 0..self.grid.height() as i32 {
+            for x in 0..self.grid.width() as i32 {
+                let p = Point { x, y };
+                if self[p] == Pipe::Ground || seen.contains(&p) {
+                    continue;
+                }
+                let (backwards, closed) = self.walk(p, None);
+                let run = if closed {
+                    PipeRun {
+                        tiles: std::iter::once(p).chain(backwards).collect(),
+                        closed,
+                    }
+                } else {
+                    // `p` may be in the middle of a chain, start from its end
+                    let end = if self.linked(p).count() < 2 {
+                        p
+                    } else {
+                        *backwards.last().unwrap()
+                    };
+                    PipeRun {
+                        tiles: std::iter::once(end)
+                            .chain(self.walk(end, None).0)
+                            .collect(),
+                        closed,
+                    }
+                };
+                seen.extend(run.tiles.iter().copied());
+                runs.push(run);
+            }
+        }
+        runs
+    }
+
+    /// Tiles enclosed by the loop `path`, found by scanning each row and
+    /// counting crossings of the loop: `|`, `L` followed by `7` and `F`
+    /// followed by `J`
+    pub fn enclosed_by_scanline(&self, path: &[Point]) -> HashSet<Point> {
+        let on_loop = path.iter().copied().collect::<HashSet<_>>();
+        let mut enclosed = HashSet::new();
+        for y in 0..self.grid.height() as i32 {
+            let mut inside = false;
+            // corner that opened the horizontal run of loop we are on
+            let mut run_start = None;
+            for x in 0..self.grid.width() as i32 {
+                let p = Point { x, y };
+                if !on_loop.contains(&p) {
+                    if inside {
+                        enclosed.insert(p);
+                    }
+                    continue;
+                }
+                match (self[p], run_start) {
+                    (Pipe::NorthSouth, _) => inside = !inside,
+                    (Pipe::NorthEast | Pipe::SouthEast, _) => run_start = Some(self[p]),
+                    (Pipe::SouthWest, Some(Pipe::NorthEast))
+                    | (Pipe::NorthWest, Some(Pipe::SouthEast)) => {
+                        inside = !inside;
+                        run_start = None;
+                    }
+                    (Pipe::SouthWest | Pipe::NorthWest, _) => run_start = None,
+                    _ => (),
+                }
+            }
+        }
+        enclosed
+    }
+
+    /// The loop `path` drawn at 3x3 cells per tile, with the centre of each
+    /// loop tile and the edges it connects to as walls, so the gaps between
+    /// adjacent pipes become paths
+    pub fn upscaled_walls(&self, path: &[Point]) -> Grid<bool> {
+        let width = self.grid.width() * 3;
+        let mut walls = Grid {
+            data: vec![false; self.grid.data.len() * 9],
+            width,
+        };
+        for &p in path {
+            let centre = Point {
+                x: p.x * 3 + 1,
+                y: p.y * 3 + 1,
+            };
+            walls[centre] = true;
+            for (dir, arm) in centre.neighbours(width as i32, walls.height() as i32) {
+                if self[p].connects(dir) {
+                    walls[arm] = true;
+                }
+            }
+        }
+        walls
+    }
+
+    /// Tiles enclosed by the loop `path`, those whose centre is not reached
+    /// when flooding the upscaled walls from the outside
+    pub fn enclosed_by_upscaling(&self, path: &[Point]) -> HashSet<Point> {
+        let walls = self.upscaled_walls(path);
+        // the outermost ring of cells is never a wall, as the loop never
+        // connects out of the grid
+        let outside = walls.get_floodfill_region(Point { x: 0, y: 0 }, |p| !walls[p]);
+        let on_loop = path.iter().copied().collect::<HashSet<_>>();
+        (0..self.grid.height() as i32)
+            .flat_map(|y| (0..self.grid.width() as i32).map(move |x| Point { x, y }))
+            .filter(|p| !on_loop.contains(p))
+            .filter(|p| {
+                !outside.contains(&Point {
+                    x: p.x * 3 + 1,
+                    y: p.y * 3 + 1,
+                })
+            })
+            .collect()
+    }
+
+    /// The pipe under the start, connecting the two neighbours that connect
+    /// back to it
+    fn start_pipe(&self) -> Result<Pipe, String> {
+        let ends = self
+            .neighbours(self.start)
+            .filter(|&(dir, p)| self[p].connects(dir.opposite()))
+            .map(|(dir, _)| dir)
+            .collect::<Vec<_>>();
+        Pipe::from_ends(&ends).ok_or(format!(
+            "start at {} connects to {} neighbours, expecting 2",
+            self.start,
+            ends.len()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn infers_start_pipe() {
+        for (file, pipe) in [
+            ("example_input.txt", Pipe::SouthEast),
+            ("test_input_3.txt", Pipe::SouthEast),
+            ("test_input_4.txt", Pipe::SouthWest),
+        ] {
+            let input = std::fs::read_to_string(file).unwrap();
+            let map: TubeMap = input.parse().unwrap();
+            assert_eq!(map[map.start], pipe, "{file}");
+        }
+        assert_eq!(
+            ".....\n.S-7.\n.|.|.\n.L-J.\n"
+                .parse::<TubeMap>()
+                .map(|m| m.start),
+            Ok(Point { x: 1, y: 1 })
+        );
+        assert_eq!(
+            "...\n.S.\n...\n".parse::<TubeMap>().map(|m| m.start),
+            Err("start at (1, 1) connects to 0 neighbours, expecting 2".to_owned())
+        );
+        assert_eq!(
+            ".|.\n-S-\n.|.\n".parse::<TubeMap>().map(|m| m.start),
+            Err("start at (1, 1) connects to 4 neighbours, expecting 2".to_owned())
+        );
+    }
+
+    #[test]
+    fn enumerates_pipe_runs() {
+        let map: TubeMap = "S7.|\nLJ.-\n.F-.\n".parse().unwrap();
+        let points = |points: &[(i32, i32)]| {
+            points
+                .iter()
+                .map(|&(x, y)| Point { x, y })
+                .collect::<Vec<_>>()
+        };
+        let runs = map.pipe_runs();
+        assert_eq!(
+            runs,
+            [
+                PipeRun {
+                    tiles: points(&[(0, 0), (1, 0), (1, 1), (0, 1)]),
+                    closed: true
+                },
+                PipeRun {
+                    tiles: points(&[(3, 0)]),
+                    closed: false
+                },
+                PipeRun {
+                    tiles: points(&[(3, 1)]),
+                    closed: false
+                },
+                PipeRun {
+                    tiles: points(&[(1, 2), (2, 2)]),
+                    closed: false
+                },
+            ]
+        );
+        assert_eq!(
+            runs[0].bounding_box(),
+            (Point { x: 0, y: 0 }, Point { x: 1, y: 1 })
+        );
+
+        // a chain entered from its middle is still listed end to end
+        let map: TubeMap = "-S-7\n...|\n".parse().unwrap();
+        assert_eq!(
+            map.pipe_runs(),
+            [PipeRun {
+                tiles: points(&[(0, 0), (1, 0), (2, 0), (3, 0), (3, 1)]),
+                closed: false
+            }]
+        );
+
+        let input = std::fs::read_to_string("test_input_3.txt").unwrap();
+        let map: TubeMap = input.parse().unwrap();
+        let loops = map
+            .pipe_runs()
+            .into_iter()
+            .filter(|r| r.closed)
+            .collect::<Vec<_>>();
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].len(), 140);
+        assert!(loops[0].tiles.contains(&map.start));
+    }
+
+    #[test]
+    fn validates_tiles() {
+        let map: TubeMap = "F7.\nSJ.\n".parse().unwrap();
+        assert_eq!(map.to_string(), "┌┐.\n└┘.\n");
+        assert_eq!(
+            "F7.\nSJX\n".parse::<TubeMap>().map(|m| m.start),
+            Err("invalid tile 'X' at (2, 1)".to_owned())
+        );
+        assert_eq!(
+            "F7.\nSJ\n".parse::<TubeMap>().map(|m| m.start),
+            Err("line 2 is 2 tiles wide, expecting 3".to_owned())
+        );
+        assert_eq!(
+            "F7S\nSJ.\n".parse::<TubeMap>().map(|m| m.start),
+            Err("second start at (0, 1)".to_owned())
+        );
+        assert_eq!(
+            "S7.\nLJD\n".parse::<TubeMap>().map(|m| m.start),
+            Err("invalid tile 'D' at (2, 1)".to_owned())
+        );
+    }
+
+    #[test]
+    fn upscales_walls() {
+        let map: TubeMap = ".....\n.S-7.\n.|.|.\n.L-J.\n.....\n".parse().unwrap();
+        let path = [
+            (1, 1),
+            (2, 1),
+            (3, 1),
+            (3, 2),
+            (3, 3),
+            (2, 3),
+            (1, 3),
+            (1, 2),
+        ]
+        .map(|(x, y)| Point { x, y });
+        let walls = map.upscaled_walls(&path);
+        let rows = (3..12)
+            .map(|y| {
+                (3..12)
+                    .map(|x| if walls[Point { x, y }] { '#' } else { '.' })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            [
+                ".........",
+                ".#######.",
+                ".#.....#.",
+                ".#.....#.",
+                ".#.....#.",
+                ".#.....#.",
+                ".#.....#.",
+                ".#######.",
+                ".........",
+            ]
+        );
+        assert_eq!(
+            map.enclosed_by_upscaling(&path),
+            HashSet::from([Point { x: 2, y: 2 }])
+        );
+    }
 }
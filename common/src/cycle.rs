@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// The sequence `initial, step(initial), step(step(initial)), ...` up to just
+/// before its first repeated state.
+///
+/// `states[prefix_len..]` repeats forever with the given `period`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cycle<S> {
+    pub prefix_len: usize,
+    pub period: usize,
+    pub states: Vec<S>,
+}
+
+impl<S> Cycle<S> {
+    /// Index into `states` of the state reached after `n` steps
+    pub fn index_of_step(&self, n: usize) -> usize {
+        if n < self.prefix_len {
+            n
+        } else {
+            self.prefix_len + (n - self.prefix_len) % self.period
+        }
+    }
+
+    /// The state reached after `n` steps
+    pub fn state_at(&self, n: usize) -> &S {
+        &self.states[self.index_of_step(n)]
+    }
+
+    /// The states that repeat forever
+    pub fn cycle_states(&self) -> &[S] {
+        &self.states[self.prefix_len..]
+    }
+}
+
+/// Find the cycle of an iterated function by remembering every state seen.
+pub fn find_cycle<S, F>(initial: S, mut step: F) -> Cycle<S>
+where
+    S: Clone + Eq + Hash,
+    F: FnMut(&S) -> S,
+{
+    let mut seen: HashMap<S, usize> = Default::default();
+    let mut states = vec![];
+    let mut state = initial;
+    loop {
+        if let Some(&prefix_len) = seen.get(&state) {
+            return Cycle {
+                prefix_len,
+                period: states.len() - prefix_len,
+                states,
+            };
+        }
+        seen.insert(state.clone(), states.len());
+        let next = step(&state);
+        states.push(state);
+        state = next;
+    }
+}
+
+/// The state after `n` steps, fast-forwarding through the cycle once it is
+/// found.
+pub fn nth_state_with_cycle<S, F>(initial: S, mut step: F, n: usize) -> S
+where
+    S: Clone + Eq + Hash,
+    F: FnMut(&S) -> S,
+{
+    let mut seen: HashMap<S, usize> = Default::default();
+    let mut states = vec![];
+    let mut state = initial;
+    for idx in 0..n {
+        if let Some(&prefix_len) = seen.get(&state) {
+            let period = idx - prefix_len;
+            return states.swap_remove(prefix_len + (n - prefix_len) % period);
+        }
+        seen.insert(state.clone(), idx);
+        let next = step(&state);
+        states.push(state);
+        state = next;
+    }
+    state
+}
+
+/// Floyd's tortoise and hare, returns `(prefix_len, period)` in constant
+/// memory.
+pub fn floyd<S, F>(initial: S, step: F) -> (usize, usize)
+where
+    S: Clone + Eq,
+    F: Fn(&S) -> S,
+{
+    let mut tortoise = step(&initial);
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&step(&hare));
+    }
+
+    let mut prefix_len = 0;
+    tortoise = initial;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        prefix_len += 1;
+    }
+
+    let mut period = 1;
+    hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        period += 1;
+    }
+    (prefix_len, period)
+}
+
+/// Brent's algorithm, returns `(prefix_len, period)` in constant memory and
+/// usually with fewer steps than [`floyd`].
+pub fn brent<S, F>(initial: S, step: F) -> (usize, usize)
+where
+    S: Clone + Eq,
+    F: Fn(&S) -> S,
+{
+    let mut power = 1;
+    let mut period = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = step(&initial);
+    while tortoise != hare {
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        hare = step(&hare);
+        period += 1;
+    }
+
+    tortoise = initial.clone();
+    hare = initial;
+    for _ in 0..period {
+        hare = step(&hare);
+    }
+    let mut prefix_len = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        prefix_len += 1;
+    }
+    (prefix_len, period)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 0 -> 1 -> 2 -> 3 -> 4 -> 5 -> 6 -> 3 -> ...
+    fn step(&x: &u32) -> u32 {
+        if x == 6 {
+            3
+        } else {
+            x + 1
+        }
+    }
+
+    #[test]
+    fn finds_cycles() {
+        let cycle = find_cycle(0, step);
+        assert_eq!(cycle.prefix_len, 3);
+        assert_eq!(cycle.period, 4);
+        assert_eq!(cycle.states, [0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(cycle.cycle_states(), [3, 4, 5, 6]);
+        assert_eq!(*cycle.state_at(2), 2);
+        assert_eq!(*cycle.state_at(7), 3);
+        assert_eq!(*cycle.state_at(1_000_000_001), 5);
+
+        assert_eq!(floyd(0, step), (3, 4));
+        assert_eq!(brent(0, step), (3, 4));
+        assert_eq!(floyd(4, step), (0, 4));
+        assert_eq!(brent(4, step), (0, 4));
+        assert_eq!(find_cycle(7, |_| 7).period, 1);
+        assert_eq!(brent(7, |_| 7), (0, 1));
+        assert_eq!(floyd(7, |_| 7), (0, 1));
+    }
+
+    #[test]
+    fn fast_forwards_through_cycles() {
+        for n in 0..50 {
+            let mut naive = 0;
+            for _ in 0..n {
+                naive = step(&naive);
+            }
+            assert_eq!(nth_state_with_cycle(0, step, n), naive, "n = {n}");
+        }
+        assert_eq!(nth_state_with_cycle(0, step, 1_000_000_001), 5);
+    }
+
+    #[test]
+    fn agrees_on_pseudo_random_sequences() {
+        for seed in 0..100u64 {
+            let modulus = 1000 + seed * 37;
+            let rng = |&x: &u64| (x * x + seed) % modulus;
+            let cycle = find_cycle(seed, rng);
+            assert_eq!(floyd(seed, rng), (cycle.prefix_len, cycle.period));
+            assert_eq!(brent(seed, rng), (cycle.prefix_len, cycle.period));
+        }
+    }
+}
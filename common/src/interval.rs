@@ -0,0 +1,416 @@
+use std::ops::Range;
+
+/// A set of integers stored as sorted, disjoint and non-adjacent half-open
+/// ranges.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    ranges: Vec<Range<usize>>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ranges(&self) -> &[Range<usize>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Number of integers in the set
+    pub fn len(&self) -> usize {
+        self.ranges.iter().map(|r| r.len()).sum()
+    }
+
+    pub fn min(&self) -> Option<usize> {
+        self.ranges.first().map(|r| r.start)
+    }
+
+    pub fn max(&self) -> Option<usize> {
+        self.ranges.last().map(|r| r.end - 1)
+    }
+
+    pub fn contains(&self, x: usize) -> bool {
+        let idx = self.ranges.partition_point(|r| r.end <= x);
+        self.ranges.get(idx).is_some_and(|r| r.contains(&x))
+    }
+
+    pub fn insert(&mut self, range: Range<usize>) {
+        *self = self.union(&IntervalSet::from(range));
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        self.ranges.iter().chain(&other.ranges).cloned().collect()
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut ranges = vec![];
+        let (mut a, mut b) = (
+            self.ranges.iter().peekable(),
+            other.ranges.iter().peekable(),
+        );
+        while let (Some(ra), Some(rb)) = (a.peek(), b.peek()) {
+            let start = ra.start.max(rb.start);
+            let end = ra.end.min(rb.end);
+            if start < end {
+                ranges.push(start..end);
+            }
+            if ra.end < rb.end {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+        IntervalSet { ranges }
+    }
+
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut ranges = vec![];
+        let mut holes = other.ranges.iter().peekable();
+        for range in &self.ranges {
+            let mut start = range.start;
+            while let Some(hole) = holes.peek() {
+                if hole.end <= start {
+                    holes.next();
+                    continue;
+                }
+                if hole.start >= range.end {
+                    break;
+                }
+                if hole.start > start {
+                    ranges.push(start..hole.start);
+                }
+                start = hole.end;
+                if hole.end > range.end {
+                    break;
+                }
+                holes.next();
+            }
+            if start < range.end {
+                ranges.push(start..range.end);
+            }
+        }
+        IntervalSet { ranges }
+    }
+}
+
+impl From<Range<usize>> for IntervalSet {
+    fn from(range: Range<usize>) -> Self {
+        std::iter::once(range).collect()
+    }
+}
+
+/// Collects arbitrary ranges, coalescing overlapping and adjacent ones.
+impl FromIterator<Range<usize>> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = Range<usize>>>(iter: I) -> Self {
+        let mut sorted = iter
+            .into_iter()
+            .filter(|r| !r.is_empty())
+            .collect::<Vec<_>>();
+        sorted.sort_by_key(|r| r.start);
+        let mut ranges: Vec<Range<usize>> = vec![];
+        for range in sorted {
+            match ranges.last_mut() {
+                Some(last) if range.start <= last.end => {
+                    last.end = last.end.max(range.end)
+                }
+                _ => ranges.push(range),
+            }
+        }
+        IntervalSet { ranges }
+    }
+}
+
+/// A segment of an [`IntervalMap`], moving `src` to start at `dst_start`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Segment {
+    pub src: Range<usize>,
+    pub dst_start: usize,
+}
+
+impl Segment {
+    pub fn map(&self, x: usize) -> usize {
+        x - self.src.start + self.dst_start
+    }
+}
+
+/// A piecewise offset map over integers, identity wherever no segment
+/// applies.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IntervalMap {
+    /// disjoint and sorted by source start
+    segments: Vec<Segment>,
+}
+
+impl IntervalMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    fn domain(&self) -> IntervalSet {
+        self.segments.iter().map(|s| s.src.clone()).collect()
+    }
+
+    /// Map `src` to start at `dst_start`. Parts of `src` already covered by
+    /// earlier segments keep their mapping.
+    pub fn insert(&mut self, src: Range<usize>, dst_start: usize) {
+        let uncovered = IntervalSet::from(src.clone()).difference(&self.domain());
+        self.segments
+            .extend(uncovered.ranges.into_iter().map(|r| Segment {
+                dst_start: r.start - src.start + dst_start,
+                src: r,
+            }));
+        self.segments.sort_by_key(|s| s.src.start);
+    }
+
+    /// Add identity segments for all parts of `domain` not yet covered
+    pub fn fill_gaps(&mut self, domain: Range<usize>) {
+        self.insert(domain.clone(), domain.start);
+    }
+
+    pub fn get(&self, x: usize) -> usize {
+        let idx = self.segments.partition_point(|s| s.src.end <= x);
+        match self.segments.get(idx) {
+            Some(segment) if segment.src.contains(&x) => segment.map(x),
+            _ => x,
+        }
+    }
+
+    /// The segments together with the identity mapped gaps between them,
+    /// covering `0..usize::MAX`
+    fn pieces(&self) -> impl Iterator<Item = Segment> + '_ {
+        let mut pos = 0;
+        self.segments
+            .iter()
+            .chain(std::iter::once(&Segment {
+                src: usize::MAX..usize::MAX,
+                dst_start: usize::MAX,
+            }))
+            .flat_map(move |segment| {
+                let gap = Segment {
+                    src: pos..segment.src.start,
+                    dst_start: pos,
+                };
+                pos = segment.src.end;
+                [gap, segment.clone()]
+            })
+            .filter(|piece| !piece.src.is_empty())
+    }
+
+    /// The map applying `self` first and `other` second
+    pub fn compose(&self, other: &IntervalMap) -> IntervalMap {
+        let mut res = IntervalMap::new();
+        for piece in self.pieces() {
+            let image = piece.dst_start..piece.map(piece.src.end - 1) + 1;
+            for other_piece in other.pieces() {
+                let start = image.start.max(other_piece.src.start);
+                let end = image.end.min(other_piece.src.end);
+                if start >= end {
+                    continue;
+                }
+                let segment = Segment {
+                    src: start - piece.dst_start + piece.src.start
+                        ..end - piece.dst_start + piece.src.start,
+                    dst_start: other_piece.map(start),
+                };
+                if segment.src.start == segment.dst_start {
+                    continue;
+                }
+                match res.segments.last_mut() {
+                    Some(last)
+                        if last.src.end == segment.src.start
+                            && last.map(last.src.end - 1) + 1 == segment.dst_start =>
+                    {
+                        last.src.end = segment.src.end
+                    }
+                    _ => res.segments.push(segment),
+                }
+            }
+        }
+        res
+    }
+
+    /// All integers that are mapped into `set`
+    pub fn preimage(&self, set: &IntervalSet) -> IntervalSet {
+        let mut res = vec![];
+        for piece in self.pieces() {
+            let image = piece.dst_start..piece.map(piece.src.end - 1) + 1;
+            let hit = set.intersection(&IntervalSet::from(image));
+            res.extend(hit.ranges.into_iter().map(|r| {
+                r.start - piece.dst_start + piece.src.start
+                    ..r.end - piece.dst_start + piece.src.start
+            }));
+        }
+        res.into_iter().collect()
+    }
+
+    /// Image of all integers in `set`
+    pub fn map_set(&self, set: &IntervalSet) -> IntervalSet {
+        let mut mapped = vec![];
+        for segment in &self.segments {
+            let covered = set.intersection(&IntervalSet::from(segment.src.clone()));
+            mapped.extend(
+                covered
+                    .ranges
+                    .into_iter()
+                    .map(|r| segment.map(r.start)..segment.map(r.end - 1) + 1),
+            );
+        }
+        mapped.extend(set.difference(&self.domain()).ranges);
+        mapped.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn coalesces() {
+        let set: IntervalSet = [5..7, 1..3, 2..4, 7..9, 10..10].into_iter().collect();
+        assert_eq!(set.ranges(), [1..4, 5..9]);
+        assert_eq!(set.len(), 7);
+        assert_eq!((set.min(), set.max()), (Some(1), Some(8)));
+        assert!(set.contains(8) && !set.contains(4) && !set.contains(9));
+    }
+
+    #[test]
+    fn maps_ranges() {
+        let mut map = IntervalMap::new();
+        map.insert(98..100, 50);
+        map.insert(50..98, 52);
+        assert_eq!(map.get(79), 81);
+        assert_eq!(map.get(14), 14);
+        assert_eq!(map.get(99), 51);
+        let seeds: IntervalSet = [79..93, 55..68].into_iter().collect();
+        assert_eq!(map.map_set(&seeds).ranges(), [57..70, 81..95]);
+        let all = IntervalSet::from(40..110);
+        assert_eq!(map.map_set(&all), all);
+    }
+
+    #[test]
+    fn earlier_segments_take_precedence() {
+        let mut map = IntervalMap::new();
+        map.insert(10..20, 100);
+        map.insert(5..25, 200);
+        assert_eq!(map.get(10), 100);
+        assert_eq!(map.get(5), 200);
+        assert_eq!(map.get(22), 217);
+        assert_eq!(map.segments().len(), 3);
+    }
+
+    #[test]
+    fn fills_gaps_with_identity() {
+        let mut map = IntervalMap::new();
+        map.insert(10..20, 100);
+        map.insert(30..40, 0);
+        map.fill_gaps(0..50);
+        let srcs = map
+            .segments()
+            .iter()
+            .map(|s| s.src.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(srcs, [0..10, 10..20, 20..30, 30..40, 40..50]);
+        assert_eq!((map.get(5), map.get(25), map.get(35)), (5, 25, 5));
+    }
+
+    const LIMIT: usize = 64;
+
+    fn ranges() -> impl Strategy<Value = Vec<Range<usize>>> {
+        prop::collection::vec((0..LIMIT, 0..16usize).prop_map(|(s, l)| s..s + l), 0..6)
+    }
+
+    fn points(set: &IntervalSet) -> BTreeSet<usize> {
+        set.ranges().iter().cloned().flatten().collect()
+    }
+
+    fn brute(ranges: &[Range<usize>]) -> BTreeSet<usize> {
+        ranges.iter().cloned().flatten().collect()
+    }
+
+    fn is_normalized(set: &IntervalSet) -> bool {
+        set.ranges().iter().all(|r| !r.is_empty())
+            && set.ranges().windows(2).all(|w| w[0].end < w[1].start)
+    }
+
+    proptest! {
+        #[test]
+        fn set_operations_match_brute_force(a in ranges(), b in ranges()) {
+            let (sa, sb) = (a.iter().cloned().collect::<IntervalSet>(),
+                            b.iter().cloned().collect::<IntervalSet>());
+            let (ba, bb) = (brute(&a), brute(&b));
+            prop_assert_eq!(points(&sa), ba.clone());
+            for set in [sa.union(&sb), sa.intersection(&sb), sa.difference(&sb)] {
+                prop_assert!(is_normalized(&set));
+            }
+            prop_assert_eq!(points(&sa.union(&sb)), &ba | &bb);
+            prop_assert_eq!(points(&sa.intersection(&sb)), &ba & &bb);
+            prop_assert_eq!(points(&sa.difference(&sb)), &ba - &bb);
+            for x in 0..LIMIT + 16 {
+                prop_assert_eq!(sa.contains(x), ba.contains(&x));
+            }
+        }
+
+        #[test]
+        fn map_matches_brute_force(
+            segments in prop::collection::vec((0..LIMIT, 0..16usize, 0..LIMIT), 0..5),
+            set in ranges(),
+        ) {
+            let mut map = IntervalMap::new();
+            for &(src, len, dst) in &segments {
+                map.insert(src..src + len, dst);
+            }
+            let brute_get = |x: usize| {
+                segments
+                    .iter()
+                    .find(|&&(src, len, _)| (src..src + len).contains(&x))
+                    .map_or(x, |&(src, _, dst)| x - src + dst)
+            };
+            for x in 0..LIMIT + 16 {
+                prop_assert_eq!(map.get(x), brute_get(x));
+            }
+            let set = set.into_iter().collect::<IntervalSet>();
+            let mapped = map.map_set(&set);
+            prop_assert!(is_normalized(&mapped));
+            prop_assert_eq!(
+                points(&mapped),
+                points(&set).into_iter().map(brute_get).collect::<BTreeSet<_>>()
+            );
+            let preimage = map.preimage(&set);
+            prop_assert!(is_normalized(&preimage));
+            prop_assert_eq!(
+                points(&preimage),
+                (0..2 * LIMIT).filter(|&x| set.contains(brute_get(x))).collect::<BTreeSet<_>>()
+            );
+        }
+
+        #[test]
+        fn compose_matches_applying_both(
+            first in prop::collection::vec((0..LIMIT, 0..16usize, 0..LIMIT), 0..5),
+            second in prop::collection::vec((0..LIMIT, 0..16usize, 0..LIMIT), 0..5),
+        ) {
+            let build = |segments: &[(usize, usize, usize)]| {
+                let mut map = IntervalMap::new();
+                for &(src, len, dst) in segments {
+                    map.insert(src..src + len, dst);
+                }
+                map
+            };
+            let (first, second) = (build(&first), build(&second));
+            let composed = first.compose(&second);
+            prop_assert!(composed.segments().windows(2).all(|w| w[0].src.end <= w[1].src.start));
+            for x in 0..2 * LIMIT + 16 {
+                prop_assert_eq!(composed.get(x), second.get(first.get(x)));
+            }
+        }
+    }
+}
@@ -1 +1,6 @@
+pub mod cycle;
+pub mod interval;
+pub mod math;
+pub mod recorder;
 pub mod twod;
+pub mod viewer;
@@ -0,0 +1,188 @@
+/// Greatest common divisor, always non-negative
+pub fn gcd(a: i128, b: i128) -> i128 {
+    extended_gcd(a, b).0
+}
+
+/// Least common multiple, always non-negative
+pub fn lcm(a: i128, b: i128) -> i128 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    (a / gcd(a, b) * b).abs()
+}
+
+/// Greatest common divisor of all numbers, 0 for none
+pub fn gcd_all(numbers: impl IntoIterator<Item = i128>) -> i128 {
+    numbers.into_iter().fold(0, gcd)
+}
+
+/// Least common multiple of all numbers, 1 for none
+pub fn lcm_all(numbers: impl IntoIterator<Item = i128>) -> i128 {
+    numbers.into_iter().fold(1, lcm)
+}
+
+/// Returns `(gcd, x, y)` with `a * x + b * y == gcd` and `gcd >= 0`
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// `x` in `0..m` with `a * x == 1 (mod m)`, if `a` and `m` are coprime
+pub fn mod_inverse(a: i128, m: i128) -> Option<i128> {
+    let (g, x, _) = extended_gcd(a.rem_euclid(m), m);
+    (g == 1).then(|| x.rem_euclid(m))
+}
+
+/// `a + b mod m` for `a` and `b` in `0..m`, without overflowing
+fn mod_add(a: i128, b: i128, m: i128) -> i128 {
+    let sum = a - (m - b);
+    if sum < 0 {
+        sum + m
+    } else {
+        sum
+    }
+}
+
+/// `a * b mod m` in `0..m` without overflowing for any positive `m`
+pub fn mod_mul(a: i128, b: i128, m: i128) -> i128 {
+    let (mut a, mut b) = (a.rem_euclid(m), b.rem_euclid(m));
+    if let Some(product) = a.checked_mul(b) {
+        return product % m;
+    }
+    let mut res = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            res = mod_add(res, a, m);
+        }
+        a = mod_add(a, a, m);
+        b >>= 1;
+    }
+    res
+}
+
+/// `base ^ exp mod m` in `0..m`
+pub fn mod_pow(base: i128, mut exp: u128, m: i128) -> i128 {
+    let mut base = base.rem_euclid(m);
+    let mut res = 1 % m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            res = mod_mul(res, base, m);
+        }
+        base = mod_mul(base, base, m);
+        exp >>= 1;
+    }
+    res
+}
+
+/// Combine `x == a1 (mod m1)` and `x == a2 (mod m2)` into
+/// `x == a (mod lcm(m1, m2))`, the moduli need not be coprime.
+///
+/// Returns `(a, lcm)` with `a` in `0..lcm`, or `None` if there is no solution.
+pub fn crt(a1: i128, m1: i128, a2: i128, m2: i128) -> Option<(i128, i128)> {
+    let (a1, a2) = (a1.rem_euclid(m1), a2.rem_euclid(m2));
+    let (g, p, _) = extended_gcd(m1, m2);
+    if (a2 - a1) % g != 0 {
+        return None;
+    }
+    let lcm = m1 / g * m2;
+    let k = mod_mul((a2 - a1) / g, p, m2 / g);
+    Some((mod_add(a1, mod_mul(m1, k, lcm), lcm), lcm))
+}
+
+/// [`crt`] over any number of congruences `(a, m)`, `(0, 1)` for none
+pub fn crt_all(
+    congruences: impl IntoIterator<Item = (i128, i128)>,
+) -> Option<(i128, i128)> {
+    congruences
+        .into_iter()
+        .try_fold((0, 1), |(a, m), (a2, m2)| crt(a, m, a2, m2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gcd_and_lcm() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(-12, 18), 6);
+        assert_eq!(gcd(0, 7), 7);
+        assert_eq!(gcd(0, 0), 0);
+        assert_eq!(lcm(4, 6), 12);
+        assert_eq!(lcm(-4, 6), 12);
+        assert_eq!(lcm(0, 6), 0);
+        assert_eq!(gcd_all([12, 18, 27]), 3);
+        assert_eq!(lcm_all([2, 3, 4, 5]), 60);
+        assert_eq!(lcm_all([]), 1);
+    }
+
+    #[test]
+    fn extended_euclid() {
+        for a in -30..30 {
+            for b in -30..30 {
+                let (g, x, y) = extended_gcd(a, b);
+                assert_eq!(a * x + b * y, g, "a = {a}, b = {b}");
+                if a != 0 || b != 0 {
+                    let brute = (1..=30).rev().find(|d| a % d == 0 && b % d == 0);
+                    assert_eq!(Some(g), brute);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn modular_arithmetic() {
+        assert_eq!(mod_inverse(3, 11), Some(4));
+        assert_eq!(mod_inverse(-3, 11), Some(7));
+        assert_eq!(mod_inverse(4, 6), None);
+        for m in 2..40 {
+            for a in 0..m {
+                match mod_inverse(a, m) {
+                    Some(inv) => assert_eq!(a * inv % m, 1),
+                    None => assert_ne!(gcd(a, m), 1),
+                }
+            }
+        }
+
+        assert_eq!(mod_pow(2, 10, 1000), 24);
+        assert_eq!(mod_pow(-2, 3, 5), 2);
+        assert_eq!(mod_pow(7, 0, 1), 0);
+        // Fermat's little theorem with a modulus whose square overflows i128
+        let p = 170141183460469231731687303715884105727; // 2^127 - 1
+        assert_eq!(mod_pow(3, p as u128 - 1, p), 1);
+        assert_eq!(mod_mul(p - 1, p - 1, p), 1);
+    }
+
+    #[test]
+    fn chinese_remainder() {
+        assert_eq!(crt(2, 3, 3, 5), Some((8, 15)));
+        assert_eq!(crt(0, 1, 4, 6), Some((4, 6)));
+        assert_eq!(crt(2, 4, 4, 6), Some((10, 12)));
+        assert_eq!(crt(1, 4, 2, 6), None);
+        assert_eq!(crt(-1, 4, 1, 6), Some((7, 12)));
+        assert_eq!(crt_all([(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt_all([]), Some((0, 1)));
+
+        // brute force over small non-coprime moduli
+        for (m1, m2) in [(4, 6), (6, 9), (5, 7), (8, 12)] {
+            for a1 in 0..m1 {
+                for a2 in 0..m2 {
+                    let expected = (0..m1 * m2).find(|x| x % m1 == a1 && x % m2 == a2);
+                    assert_eq!(crt(a1, m1, a2, m2).map(|(a, _)| a), expected);
+                }
+            }
+        }
+    }
+}
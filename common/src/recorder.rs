@@ -0,0 +1,284 @@
+use std::io::Write;
+use std::time::Duration;
+
+use crate::twod::{Grid, Point, RowIdx};
+
+/// One recorded step, a grid snapshot rendered to glyphs and/or a text note.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub grid: Option<Grid<char>>,
+    pub note: String,
+}
+
+/// Collects the intermediate states of an iterative solver for later replay.
+///
+/// A disabled recorder ignores everything pushed to it, so solvers can take a
+/// `&mut Recorder` unconditionally.
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    frames: Vec<Frame>,
+    enabled: bool,
+    stride: usize,
+    pushes: usize,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Recorder {
+            frames: vec![],
+            enabled: true,
+            stride: 1,
+            pushes: 0,
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Recorder {
+            enabled: false,
+            ..Self::new()
+        }
+    }
+
+    /// Only keep every `stride`-th pushed frame, the first one is always kept.
+    pub fn every(mut self, stride: usize) -> Self {
+        self.stride = stride.max(1);
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    fn should_keep(&mut self) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        self.pushes += 1;
+        (self.pushes - 1).is_multiple_of(self.stride)
+    }
+
+    /// Record a snapshot of `grid`, `glyph` decides how each cell is drawn.
+    pub fn push_grid<T, F>(&mut self, grid: &Grid<T>, glyph: F, note: impl Into<String>)
+    where
+        T: Copy,
+        F: Fn(Point, T) -> char,
+    {
+        if !self.should_keep() {
+            return;
+        }
+        let data = (0..grid.height())
+            .flat_map(|y| (0..grid.width()).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let p = Point {
+                    x: x as i32,
+                    y: y as i32,
+                };
+                glyph(p, grid[p])
+            })
+            .collect();
+        self.frames.push(Frame {
+            grid: Some(Grid {
+                data,
+                width: grid.width(),
+            }),
+            note: note.into(),
+        });
+    }
+
+    /// Record a frame that only carries a text note.
+    pub fn push_note(&mut self, note: impl Into<String>) {
+        if !self.should_keep() {
+            return;
+        }
+        self.frames.push(Frame {
+            grid: None,
+            note: note.into(),
+        });
+    }
+
+    /// Append a line of text to the most recent frame.
+    pub fn annotate(&mut self, text: &str) {
+        if let Some(frame) = self.frames.last_mut() {
+            if !frame.note.is_empty() {
+                frame.note.push('\n');
+            }
+            frame.note.push_str(text);
+        }
+    }
+
+    /// Dump all frames as plain text, each preceded by a header line.
+    pub fn write_text<W: Write>(&self, w: &mut W) -> std::io::Result<()> {
+        for (idx, frame) in self.frames.iter().enumerate() {
+            writeln!(w, "--- frame {} ---", idx + 1)?;
+            write_frame(w, frame)?;
+        }
+        Ok(())
+    }
+
+    /// Play the frames back in the terminal, clearing the screen in between.
+    pub fn replay(&self, delay: Duration) -> std::io::Result<()> {
+        let mut stdout = std::io::stdout().lock();
+        for (idx, frame) in self.frames.iter().enumerate() {
+            write!(stdout, "\x1b[2J\x1b[H")?;
+            writeln!(stdout, "frame {}/{}", idx + 1, self.frames.len())?;
+            write_frame(&mut stdout, frame)?;
+            stdout.flush()?;
+            std::thread::sleep(delay);
+        }
+        Ok(())
+    }
+
+    /// Render the grid frames as an animated GIF, drawing every cell as a
+    /// `cell_size` square colored by `color`. Note-only frames are skipped.
+    pub fn write_gif<W, F>(
+        &self,
+        w: W,
+        cell_size: usize,
+        delay: Duration,
+        color: F,
+    ) -> Result<(), String>
+    where
+        W: Write,
+        F: Fn(char) -> [u8; 3],
+    {
+        let grids = self
+            .frames
+            .iter()
+            .filter_map(|f| f.grid.as_ref())
+            .collect::<Vec<_>>();
+        let cols = grids.iter().map(|g| g.width()).max().unwrap_or(0);
+        let rows = grids.iter().map(|g| g.height()).max().unwrap_or(0);
+        let width: u16 = (cols * cell_size)
+            .try_into()
+            .map_err(|_| "grid too wide for a GIF".to_owned())?;
+        let height: u16 = (rows * cell_size)
+            .try_into()
+            .map_err(|_| "grid too high for a GIF".to_owned())?;
+
+        // index 0 is the background outside of smaller grids
+        let mut palette: Vec<[u8; 3]> = vec![[0, 0, 0]];
+        let mut frames = vec![];
+        for grid in grids {
+            let mut pixels = vec![0u8; width as usize * height as usize];
+            for y in 0..grid.height() {
+                for (x, &c) in grid[RowIdx { idx: y }].iter().enumerate() {
+                    let rgb = color(c);
+                    let idx = match palette.iter().position(|&p| p == rgb) {
+                        Some(idx) => idx,
+                        None if palette.len() < 256 => {
+                            palette.push(rgb);
+                            palette.len() - 1
+                        }
+                        None => return Err("more than 256 colors".to_owned()),
+                    };
+                    for py in y * cell_size..(y + 1) * cell_size {
+                        let row_start = py * width as usize;
+                        pixels
+                            [row_start + x * cell_size..row_start + (x + 1) * cell_size]
+                            .fill(idx as u8);
+                    }
+                }
+            }
+            frames.push(pixels);
+        }
+
+        let flat_palette = palette.iter().flatten().copied().collect::<Vec<_>>();
+        let mut encoder = gif::Encoder::new(w, width, height, &flat_palette)
+            .map_err(|e| e.to_string())?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(|e| e.to_string())?;
+        let delay_cs = (delay.as_millis() / 10).min(u16::MAX as u128) as u16;
+        for pixels in frames {
+            let mut frame = gif::Frame::from_indexed_pixels(width, height, pixels, None);
+            frame.delay = delay_cs;
+            encoder.write_frame(&frame).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+fn write_frame<W: Write>(w: &mut W, frame: &Frame) -> std::io::Result<()> {
+    if let Some(grid) = &frame.grid {
+        for y in 0..grid.height() {
+            writeln!(w, "{}", grid[RowIdx { idx: y }].iter().collect::<String>())?;
+        }
+    }
+    if !frame.note.is_empty() {
+        writeln!(w, "{}", frame.note)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> Grid<u8> {
+        Grid {
+            data: b"ab.cd.".to_vec(),
+            width: 3,
+        }
+    }
+
+    #[test]
+    fn records_text_frames() {
+        let mut rec = Recorder::new();
+        rec.push_grid(&grid(), |_, c| c as char, "first");
+        rec.push_grid(&grid(), |p, c| if p.y == 1 { '#' } else { c as char }, "");
+        rec.annotate("second");
+        rec.push_note("done");
+        let mut out = vec![];
+        rec.write_text(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "--- frame 1 ---\nab.\ncd.\nfirst\n\
+             --- frame 2 ---\nab.\n###\nsecond\n\
+             --- frame 3 ---\ndone\n"
+        );
+    }
+
+    #[test]
+    fn disabled_and_strided_recording() {
+        let mut rec = Recorder::disabled();
+        rec.push_note("ignored");
+        assert!(rec.frames().is_empty());
+
+        let mut rec = Recorder::new().every(3);
+        for i in 0..7 {
+            rec.push_note(i.to_string());
+        }
+        let notes = rec
+            .frames()
+            .iter()
+            .map(|f| f.note.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(notes, ["0", "3", "6"]);
+    }
+
+    #[test]
+    fn writes_gif() {
+        let mut rec = Recorder::new();
+        rec.push_grid(&grid(), |_, c| c as char, "");
+        rec.push_note("not rendered");
+        let mut out = vec![];
+        rec.write_gif(&mut out, 2, Duration::from_millis(100), |c| match c {
+            '.' => [0, 0, 0],
+            _ => [255, 255, 255],
+        })
+        .unwrap();
+        assert!(out.starts_with(b"GIF89a"));
+        // logical screen size is 3x2 cells of 2 pixels each
+        assert_eq!(&out[6..10], &[6, 0, 4, 0]);
+    }
+}
@@ -140,7 +140,7 @@ where
 
     pub fn fill_path(&mut self, path: &[Point], item: T) {
         for segment in path.windows(2) {
-            self.fill_line(*segment.get(0).unwrap(), *segment.get(1).unwrap(), item);
+            self.fill_line(segment[0], segment[1], item);
         }
     }
 
@@ -166,8 +166,7 @@ where
         }
         let mut to_visit = vec![start];
         let mut visited = HashSet::new();
-        while !to_visit.is_empty() {
-            let p = to_visit.pop().unwrap();
+        while let Some(p) = to_visit.pop() {
             visited.insert(p);
             for (_, n) in p.neighbours(self.width as i32, self.height() as i32) {
                 if !visited.contains(&n) && predicate(n) {
@@ -181,9 +180,9 @@ where
     pub fn mirror_rows(&mut self) {
         let mut buf: Vec<T>;
         for row in 0..self.height() {
-            buf = self[RowIdx { idx: row }].iter().cloned().collect();
+            buf = self[RowIdx { idx: row }].to_vec();
             for col in 0..self.width {
-                self[RowIdx { idx: row }][col] = buf[self.width - col];
+                self[RowIdx { idx: row }][col] = buf[self.width - 1 - col];
             }
         }
     }
@@ -193,3 +192,20 @@ where
         self.mirror_rows();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mirrors() {
+        let mut grid = Grid {
+            data: b"abcdef".to_vec(),
+            width: 3,
+        };
+        grid.mirror_rows();
+        assert_eq!(grid.data, b"cbafed");
+        grid.mirror_columns();
+        assert_eq!(grid.data, b"fedcba");
+    }
+}
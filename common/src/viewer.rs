@@ -0,0 +1,354 @@
+use std::collections::HashSet;
+use std::io::Write;
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind},
+    queue,
+    style::{self, Attribute, Color},
+    terminal,
+};
+
+use crate::twod::{Grid, Point};
+
+const OVERLAY_COLORS: [Color; 6] = [
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+];
+
+/// A named set of points highlighted on top of the grid.
+struct Overlay {
+    name: String,
+    points: HashSet<Point>,
+    visible: bool,
+}
+
+/// A screen cell as produced by [`Viewer::render`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cell {
+    pub ch: char,
+    /// index of the topmost visible overlay covering this cell
+    pub overlay: Option<usize>,
+    pub cursor: bool,
+}
+
+/// Interactive terminal viewer for a grid plus named point sets.
+///
+/// Arrow keys or hjkl move the cursor (HJKL and PageUp/PageDown move faster),
+/// `+`/`-` zoom, digits toggle overlays and `q` quits. When zoomed out, each
+/// screen cell shows the top left tile of its block, or the overlay if any
+/// tile in the block is covered.
+pub struct Viewer<'a, T> {
+    grid: &'a Grid<T>,
+    glyph: Box<dyn Fn(T) -> char + 'a>,
+    overlays: Vec<Overlay>,
+    cursor: Point,
+    /// top left corner of the viewport in grid coordinates
+    origin: Point,
+    /// positive: tiles per screen cell, negative: screen cells per tile
+    zoom: i32,
+}
+
+impl<'a, T> Viewer<'a, T>
+where
+    T: Copy + std::fmt::Debug,
+{
+    pub fn new(grid: &'a Grid<T>, glyph: impl Fn(T) -> char + 'a) -> Self {
+        Viewer {
+            grid,
+            glyph: Box::new(glyph),
+            overlays: vec![],
+            cursor: Point { x: 0, y: 0 },
+            origin: Point { x: 0, y: 0 },
+            zoom: 1,
+        }
+    }
+
+    pub fn with_overlay(
+        mut self,
+        name: &str,
+        points: impl IntoIterator<Item = Point>,
+    ) -> Self {
+        self.overlays.push(Overlay {
+            name: name.to_owned(),
+            points: points.into_iter().collect(),
+            visible: true,
+        });
+        self
+    }
+
+    pub fn with_cursor(mut self, cursor: Point) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    /// Tiles per screen cell along each axis
+    fn tiles_per_cell(&self) -> i32 {
+        self.zoom.max(1)
+    }
+
+    /// Screen cells per tile along each axis
+    fn cells_per_tile(&self) -> i32 {
+        (-self.zoom).max(1)
+    }
+
+    fn zoom_in(&mut self) {
+        self.zoom = match self.zoom {
+            1 => -2,
+            z if z > 1 => z / 2,
+            z => (z * 2).max(-4),
+        };
+    }
+
+    fn zoom_out(&mut self) {
+        self.zoom = match self.zoom {
+            -2 => 1,
+            z if z < -2 => z / 2,
+            z => (z * 2).min(64),
+        };
+    }
+
+    pub fn move_cursor(&mut self, dx: i32, dy: i32) {
+        let step = self.tiles_per_cell();
+        self.cursor = Point {
+            x: (self.cursor.x + dx * step).clamp(0, self.grid.width() as i32 - 1),
+            y: (self.cursor.y + dy * step).clamp(0, self.grid.height() as i32 - 1),
+        };
+    }
+
+    pub fn toggle_overlay(&mut self, idx: usize) {
+        if let Some(overlay) = self.overlays.get_mut(idx) {
+            overlay.visible = !overlay.visible;
+        }
+    }
+
+    /// Scroll the viewport so that the cursor is visible in a `cols` x `rows`
+    /// screen area.
+    fn scroll_to_cursor(&mut self, cols: usize, rows: usize) {
+        let span_x = (cols as i32 / self.cells_per_tile()).max(1) * self.tiles_per_cell();
+        let span_y = (rows as i32 / self.cells_per_tile()).max(1) * self.tiles_per_cell();
+        if self.cursor.x < self.origin.x || self.cursor.x >= self.origin.x + span_x {
+            self.origin.x = (self.cursor.x - span_x / 2).max(0);
+        }
+        if self.cursor.y < self.origin.y || self.cursor.y >= self.origin.y + span_y {
+            self.origin.y = (self.cursor.y - span_y / 2).max(0);
+        }
+    }
+
+    fn overlay_at(&self, block: Point, size: i32) -> Option<usize> {
+        self.overlays
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(idx, ov)| {
+                let covered = ov.visible
+                    && (0..size).any(|dy| {
+                        (0..size).any(|dx| {
+                            ov.points.contains(&Point {
+                                x: block.x + dx,
+                                y: block.y + dy,
+                            })
+                        })
+                    });
+                covered.then_some(idx)
+            })
+    }
+
+    /// Render the visible part of the grid into `rows` lines of at most `cols`
+    /// cells.
+    pub fn render(&mut self, cols: usize, rows: usize) -> Vec<Vec<Cell>> {
+        self.scroll_to_cursor(cols, rows);
+        let (tiles, cells) = (self.tiles_per_cell(), self.cells_per_tile());
+        let (width, height) = (self.grid.width() as i32, self.grid.height() as i32);
+        let mut lines = vec![];
+        for row in 0..rows as i32 {
+            let y = self.origin.y + row / cells * tiles;
+            if y >= height {
+                break;
+            }
+            let mut line = vec![];
+            for col in 0..cols as i32 {
+                let x = self.origin.x + col / cells * tiles;
+                if x >= width {
+                    break;
+                }
+                let block = Point { x, y };
+                let cursor = (x..x + tiles).contains(&self.cursor.x)
+                    && (y..y + tiles).contains(&self.cursor.y);
+                line.push(Cell {
+                    ch: (self.glyph)(self.grid[block]),
+                    overlay: self.overlay_at(block, tiles),
+                    cursor,
+                });
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    pub fn status_line(&self) -> String {
+        let value = self.grid[self.cursor];
+        let zoom = match self.zoom {
+            z if z < 0 => format!("{}x", -z),
+            1 => "1x".to_owned(),
+            z => format!("1/{z}x"),
+        };
+        let overlays = self
+            .overlays
+            .iter()
+            .enumerate()
+            .map(|(idx, ov)| {
+                let mark = if ov.visible { '*' } else { ' ' };
+                format!("[{}{mark}] {} ({})", idx + 1, ov.name, ov.points.len())
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!(
+            "{} = {:?} '{}' | zoom {zoom} | {overlays}",
+            self.cursor,
+            value,
+            (self.glyph)(value)
+        )
+    }
+
+    /// Take over the terminal until the user quits.
+    pub fn run(mut self) -> std::io::Result<()> {
+        let mut stdout = std::io::stdout();
+        terminal::enable_raw_mode()?;
+        queue!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+        let res = self.event_loop(&mut stdout);
+        queue!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
+        stdout.flush()?;
+        terminal::disable_raw_mode()?;
+        res
+    }
+
+    fn event_loop(&mut self, out: &mut impl Write) -> std::io::Result<()> {
+        loop {
+            self.draw(out)?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let page = (terminal::size()?.1 as i32 / 2).max(1);
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Left | KeyCode::Char('h') => self.move_cursor(-1, 0),
+                KeyCode::Right | KeyCode::Char('l') => self.move_cursor(1, 0),
+                KeyCode::Up | KeyCode::Char('k') => self.move_cursor(0, -1),
+                KeyCode::Down | KeyCode::Char('j') => self.move_cursor(0, 1),
+                KeyCode::Char('H') => self.move_cursor(-10, 0),
+                KeyCode::Char('L') => self.move_cursor(10, 0),
+                KeyCode::Char('K') => self.move_cursor(0, -10),
+                KeyCode::Char('J') => self.move_cursor(0, 10),
+                KeyCode::PageUp => self.move_cursor(0, -page),
+                KeyCode::PageDown => self.move_cursor(0, page),
+                KeyCode::Char('+') => self.zoom_in(),
+                KeyCode::Char('-') => self.zoom_out(),
+                KeyCode::Char(c @ '1'..='9') => {
+                    self.toggle_overlay(c as usize - '1' as usize)
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn draw(&mut self, out: &mut impl Write) -> std::io::Result<()> {
+        let (cols, rows) = terminal::size()?;
+        let lines = self.render(cols as usize, rows.saturating_sub(1) as usize);
+        queue!(out, terminal::Clear(terminal::ClearType::All))?;
+        for (row, line) in lines.iter().enumerate() {
+            queue!(out, cursor::MoveTo(0, row as u16))?;
+            for cell in line {
+                if let Some(idx) = cell.overlay {
+                    let color = OVERLAY_COLORS[idx % OVERLAY_COLORS.len()];
+                    queue!(out, style::SetForegroundColor(color))?;
+                }
+                if cell.cursor {
+                    queue!(out, style::SetAttribute(Attribute::Reverse))?;
+                }
+                queue!(out, style::Print(cell.ch), style::ResetColor)?;
+                queue!(out, style::SetAttribute(Attribute::Reset))?;
+            }
+        }
+        let mut status = self.status_line();
+        status.truncate(
+            status
+                .char_indices()
+                .nth(cols as usize)
+                .map_or(status.len(), |(idx, _)| idx),
+        );
+        queue!(
+            out,
+            cursor::MoveTo(0, rows.saturating_sub(1)),
+            style::Print(status)
+        )?;
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> Grid<u8> {
+        Grid {
+            data: b"abcdefghijklmnop".to_vec(),
+            width: 4,
+        }
+    }
+
+    fn chars(lines: &[Vec<Cell>]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|l| l.iter().map(|c| c.ch).collect())
+            .collect()
+    }
+
+    #[test]
+    fn renders_and_scrolls() {
+        let grid = grid();
+        let mut viewer = Viewer::new(&grid, |c| c as char);
+        assert_eq!(
+            chars(&viewer.render(10, 10)),
+            ["abcd", "efgh", "ijkl", "mnop"]
+        );
+        viewer.move_cursor(3, 3);
+        assert_eq!(chars(&viewer.render(2, 2)), ["kl", "op"]);
+        assert!(viewer.status_line().starts_with("(3, 3) = 112 'p'"));
+    }
+
+    #[test]
+    fn zooms() {
+        let grid = grid();
+        let mut viewer = Viewer::new(&grid, |c| c as char)
+            .with_overlay("corner", [Point { x: 3, y: 3 }]);
+        viewer.zoom_out();
+        let lines = viewer.render(10, 10);
+        assert_eq!(chars(&lines), ["ac", "ik"]);
+        assert_eq!(lines[1][1].overlay, Some(0));
+        assert_eq!(lines[0][1].overlay, None);
+        viewer.zoom_in();
+        viewer.zoom_in();
+        assert_eq!(chars(&viewer.render(4, 2)), ["aabb", "aabb"]);
+    }
+
+    #[test]
+    fn toggles_overlays() {
+        let grid = grid();
+        let mut viewer = Viewer::new(&grid, |c| c as char)
+            .with_overlay("a", [Point { x: 1, y: 0 }])
+            .with_overlay("b", [Point { x: 1, y: 0 }]);
+        assert_eq!(viewer.render(4, 1)[0][1].overlay, Some(1));
+        viewer.toggle_overlay(1);
+        assert_eq!(viewer.render(4, 1)[0][1].overlay, Some(0));
+        viewer.toggle_overlay(0);
+        assert_eq!(viewer.render(4, 1)[0][1].overlay, None);
+    }
+}